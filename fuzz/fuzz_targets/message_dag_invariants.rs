@@ -0,0 +1,115 @@
+#![no_main]
+
+use std::collections::HashSet;
+
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+
+use core_cbc_casper::justification::LatestMessages;
+use core_cbc_casper::message::Message;
+use core_cbc_casper::tests_common::vote_count::VoteCount;
+use core_cbc_casper::validator;
+
+/// A small sequence of "validator casts a vote" / "validator observes the network and
+/// sends its own message" operations, bounded to a handful of validators and rounds so
+/// the generated DAGs stay shrinkable.
+#[derive(Debug, Arbitrary)]
+struct FuzzOps(Vec<FuzzOp>);
+
+#[derive(Debug, Arbitrary)]
+enum FuzzOp {
+    /// Validator `.0 % NUM_VALIDATORS` casts a brand new root vote for `.1`.
+    Vote(u8, bool),
+    /// Validator `.0 % NUM_VALIDATORS` sends a message built from everything it has seen
+    /// so far.
+    Send(u8),
+}
+
+const NUM_VALIDATORS: u8 = 4;
+
+fuzz_target!(|ops: FuzzOps| {
+    let weights = validator::Weights::new(
+        (0..NUM_VALIDATORS as u32)
+            .map(|v| (v, 1.0))
+            .collect(),
+    );
+
+    let mut state = validator::State::new(
+        weights,
+        0.0,
+        LatestMessages::empty(),
+        // Tolerate at most one faulty validator's worth of weight so equivocating
+        // messages are still admitted into the DAG instead of being silently dropped.
+        1.0,
+        HashSet::new(),
+    );
+
+    // `created` preserves creation order, which lets us assert `depends` never points
+    // from an earlier message to one created later.
+    let mut created: Vec<Message<VoteCount>> = Vec::new();
+
+    for op in ops.0.iter().take(64) {
+        match *op {
+            FuzzOp::Vote(v, value) => {
+                let validator = (v % NUM_VALIDATORS) as u32;
+                let vote = VoteCount::create_vote_message(validator, value);
+                state.update(&[&vote]);
+                created.push(vote);
+            }
+            FuzzOp::Send(v) => {
+                let validator = (v % NUM_VALIDATORS) as u32;
+                if let Ok(msg) = Message::from_validator_state(validator, &state) {
+                    state.update(&[&msg]);
+                    created.push(msg);
+                }
+            }
+        }
+    }
+
+    for (i, a) in created.iter().enumerate() {
+        // Irreflexivity: a message never depends on itself.
+        assert!(!a.depends(a), "depends must be irreflexive");
+
+        for (j, b) in created.iter().enumerate() {
+            if i == j {
+                continue;
+            }
+
+            // A message can never depend on one that was created strictly later.
+            if a.depends(b) {
+                assert!(
+                    i > j,
+                    "depends must not point from an earlier message to a later one"
+                );
+            }
+
+            // equivocates is symmetric.
+            assert_eq!(
+                a.equivocates(b),
+                b.equivocates(a),
+                "equivocates must be symmetric"
+            );
+
+            // detect_equivocators is commutative.
+            assert_eq!(
+                a.detect_equivocators(b),
+                b.detect_equivocators(a),
+                "detect_equivocators must be commutative"
+            );
+        }
+    }
+
+    // Transitivity of depends.
+    for a in &created {
+        for b in &created {
+            if !a.depends(b) {
+                continue;
+            }
+            for c in &created {
+                if b.depends(c) {
+                    assert!(a.depends(c), "depends must be transitive");
+                }
+            }
+        }
+    }
+});
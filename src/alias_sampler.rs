@@ -0,0 +1,95 @@
+use rand::Rng;
+
+use senders_weight::SendersWeight;
+use weight_unit::WeightUnit;
+
+/// O(1) weighted sampling of a validator set via Vose's alias method.
+///
+/// Building the sampler is O(n) in the number of validators (amortized over the two
+/// worklists below), after which every `sample` call is O(1), regardless of how skewed
+/// the weight distribution is. This is meant for call sites that need to repeatedly draw
+/// a random validator proportionally to stake, e.g. a randomized proposer/leader
+/// election, where re-deriving a weighted median per draw (as `mk_estimate` does today)
+/// would be wasteful.
+pub struct AliasSampler<Validator> {
+    validators: Vec<Validator>,
+    prob: Vec<f64>,
+    alias: Vec<usize>,
+}
+
+impl<Validator: Clone + Eq + std::hash::Hash> AliasSampler<Validator> {
+    /// Builds the alias table from a set of validator weights. Zero-weight validators are
+    /// excluded from the table. Returns an error if there is nothing left to sample from.
+    pub fn new(senders_weight: &SendersWeight<Validator>) -> Result<Self, &'static str> {
+        let senders = senders_weight.get_senders()?;
+
+        let weighted: Vec<(Validator, WeightUnit)> = senders
+            .into_iter()
+            .filter_map(|sender| {
+                let weight = senders_weight.get_weight(&sender).unwrap_or(WeightUnit::ZERO);
+                if weight > WeightUnit::ZERO {
+                    Some((sender, weight))
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        if weighted.is_empty() {
+            return Err("cannot build an alias sampler from an empty or all-zero weight set");
+        }
+
+        let n = weighted.len();
+        let total_weight: WeightUnit = weighted.iter().map(|(_, w)| w).sum();
+
+        let validators: Vec<Validator> = weighted.iter().map(|(v, _)| v.clone()).collect();
+        // p_i = n * w_i / W
+        let mut scaled: Vec<f64> = weighted
+            .iter()
+            .map(|(_, w)| (n as f64) * (*w as f64) / (total_weight as f64))
+            .collect();
+
+        let mut prob = vec![0.0; n];
+        let mut alias = vec![0usize; n];
+
+        let mut small: Vec<usize> = Vec::new();
+        let mut large: Vec<usize> = Vec::new();
+        for (i, p) in scaled.iter().enumerate() {
+            if *p < 1.0 {
+                small.push(i);
+            } else {
+                large.push(i);
+            }
+        }
+
+        while let (Some(s), Some(l)) = (small.pop(), large.pop()) {
+            prob[s] = scaled[s];
+            alias[s] = l;
+            scaled[l] -= 1.0 - scaled[s];
+            if scaled[l] < 1.0 {
+                small.push(l);
+            } else {
+                large.push(l);
+            }
+        }
+        // Leftovers only carry rounding error; they are certain outcomes on their own.
+        for i in large.into_iter().chain(small.into_iter()) {
+            prob[i] = 1.0;
+        }
+
+        Ok(AliasSampler {
+            validators,
+            prob,
+            alias,
+        })
+    }
+
+    /// Draws a single validator, weighted by the stake used to build this sampler.
+    pub fn sample<R: Rng>(&self, rng: &mut R) -> Validator {
+        let n = self.validators.len();
+        let i = rng.gen_range(0, n);
+        let f: f64 = rng.gen();
+        let picked = if f < self.prob[i] { i } else { self.alias[i] };
+        self.validators[picked].clone()
+    }
+}
@@ -0,0 +1,133 @@
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::ops::Add;
+
+use crate::estimator::Estimator;
+use crate::justification::LatestMessages;
+use crate::message::Message;
+use crate::util::hash::Hash;
+use crate::util::id::Id;
+use crate::util::weight::WeightUnit;
+use crate::validator;
+
+/// A point-in-time snapshot of consensus health derived from a `validator::State`, in
+/// the spirit of a health dashboard: who is detected equivocating and by how much, who
+/// is lagging behind the frontier, and who is actively participating. Built once via
+/// [`Report::from_state`] rather than kept live, so operators can inspect it without
+/// manually walking `Justification` graphs themselves.
+///
+/// [`Report::from_state`]: #method.from_state
+pub struct Report<E: Estimator, U> {
+    /// Equivocators and their weight, heaviest first.
+    pub equivocators_by_weight: Vec<(E::ValidatorName, U)>,
+    /// Total equivocation weight accrued so far.
+    pub total_fault_weight: U,
+    /// The `State`'s configured fault tolerance threshold, for comparison against
+    /// `total_fault_weight`.
+    pub fault_threshold: U,
+    /// Validators ranked by staleness: how many justification hops behind the deepest
+    /// known message their own latest message sits, least stale (most caught-up) first.
+    pub validators_by_staleness: Vec<(E::ValidatorName, usize)>,
+    /// Validators ranked by participation: the number of their distinct messages
+    /// reachable from the frontier, most active first.
+    pub validators_by_participation: Vec<(E::ValidatorName, usize)>,
+}
+
+impl<E, U> Report<E, U>
+where
+    E: Estimator,
+    U: WeightUnit + Copy + Add<Output = U> + PartialOrd,
+{
+    /// Whether accrued equivocation weight has crossed the `State`'s fault tolerance.
+    pub fn over_fault_threshold(&self) -> bool {
+        self.total_fault_weight > self.fault_threshold
+    }
+
+    pub fn from_state(state: &validator::State<E, U>) -> Self {
+        let equivocators = state.equivocators();
+        let weights = state.validators_weights();
+
+        let mut equivocators_by_weight: Vec<(E::ValidatorName, U)> = equivocators
+            .iter()
+            .map(|sender| (sender.clone(), weights.weight(sender).unwrap_or(U::ZERO)))
+            .collect();
+        equivocators_by_weight
+            .sort_unstable_by(|(_, a), (_, b)| b.partial_cmp(a).unwrap_or(Ordering::Equal));
+        let total_fault_weight = equivocators_by_weight
+            .iter()
+            .fold(U::ZERO, |acc, (_, w)| acc + *w);
+
+        let (depth, participation) = Self::walk_justifications(state.latests_messages());
+        let max_depth = depth.values().copied().max().unwrap_or(0);
+
+        let mut validators_by_staleness: Vec<(E::ValidatorName, usize)> = state
+            .latests_messages()
+            .iter()
+            .map(|(sender, msgs)| {
+                let own_depth = msgs
+                    .iter()
+                    .filter_map(|m| depth.get(&m.id()).copied())
+                    .max()
+                    .unwrap_or(0);
+                (sender.clone(), max_depth - own_depth)
+            })
+            .collect();
+        validators_by_staleness.sort_unstable_by_key(|(_, staleness)| *staleness);
+
+        let mut validators_by_participation: Vec<(E::ValidatorName, usize)> =
+            participation.into_iter().collect();
+        validators_by_participation.sort_unstable_by(|(_, a), (_, b)| b.cmp(a));
+
+        Report {
+            equivocators_by_weight,
+            total_fault_weight,
+            fault_threshold: state.fault_threshold(),
+            validators_by_staleness,
+            validators_by_participation,
+        }
+    }
+
+    /// Walks the transitive justification closure reachable from every validator's
+    /// latest message exactly once, computing each message's depth (longest path to a
+    /// root) and each sender's participation count (distinct messages of theirs found in
+    /// the closure).
+    fn walk_justifications(
+        latest_messages: &LatestMessages<Message<E>>,
+    ) -> (HashMap<Hash, usize>, HashMap<E::ValidatorName, usize>) {
+        fn visit<E: Estimator>(
+            msg: &Message<E>,
+            depth: &mut HashMap<Hash, usize>,
+            participation: &mut HashMap<E::ValidatorName, usize>,
+            visited: &mut HashSet<Hash>,
+        ) -> usize {
+            if let Some(d) = depth.get(&msg.id()) {
+                return *d;
+            }
+            if !visited.insert(msg.id()) {
+                return 0;
+            }
+            *participation.entry(msg.sender().clone()).or_insert(0) += 1;
+
+            let own_depth = msg
+                .justification()
+                .iter()
+                .map(|parent| 1 + visit(parent, depth, participation, visited))
+                .max()
+                .unwrap_or(0);
+            depth.insert(msg.id(), own_depth);
+            own_depth
+        }
+
+        let mut depth = HashMap::new();
+        let mut participation = HashMap::new();
+        let mut visited = HashSet::new();
+
+        for msgs in latest_messages.values() {
+            for msg in msgs {
+                visit(msg, &mut depth, &mut participation, &mut visited);
+            }
+        }
+
+        (depth, participation)
+    }
+}
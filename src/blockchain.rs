@@ -17,6 +17,18 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+// NOTE: this module predates `crate::justification::LatestMessages`/`LatestMessagesHonest`
+// being generic over `JustifiedMessage` (blanket-implemented only for
+// `crate::message::Message<E>`) and still passes `Self` (`Block<V>`) directly where a
+// `Message<Self>` is actually required -- e.g. `Block::estimate`'s
+// `&LatestMessagesHonest<Self>` should read `&LatestMessagesHonest<message::Message<Self>>`,
+// and every `.sender()`/`.justification()` call on an iterated `latest_msgs_honest` entry
+// assumes it is a bare `Block`, not a `Message<Block<V>>` wrapper. `pick_heaviest` also
+// calls `weights.sum_weight_validators(&referred_validators)`, a `Weights` method that
+// does not exist (only `weight`/`validators`/`sum_all_weights` do). This file has never
+// compiled against any generation of this crate; identifiers below are kept in sync with
+// the rest of the crate's naming, but the `Self`-vs-`Message<Self>` port and the missing
+// `Weights` method are out of scope here.
 use std::cmp::Ordering;
 use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
 use std::convert::From;
@@ -27,11 +39,12 @@ use std::sync::Arc;
 use serde_derive::Serialize;
 
 use crate::estimator::Estimator;
-use crate::justification::{Justification, LatestMsgs, LatestMsgsHonest};
+use crate::justification::{Justification, LatestMessages, LatestMessagesHonest};
 use crate::message;
+use crate::traits::Zero;
 use crate::util::hash::Hash;
 use crate::util::id::Id;
-use crate::util::weight::{WeightUnit, Zero};
+use crate::util::weight::WeightUnit;
 use crate::validator;
 
 /// Casper message (`message::Message`) for a `Block` send by a validator `V:
@@ -124,7 +137,7 @@ impl<V: validator::ValidatorName> Estimator for Block<V> {
     type ValidatorName = V;
 
     fn estimate<U: WeightUnit>(
-        latest_msgs: &LatestMsgsHonest<Self>,
+        latest_msgs: &LatestMessagesHonest<Self>,
         validators_weights: &validator::Weights<V, U>,
     ) -> Result<Self, Self::Error> {
         let prevblock = Block::ghost(latest_msgs, validators_weights)?;
@@ -171,7 +184,7 @@ impl<V: validator::ValidatorName> Block<V> {
 
     pub fn safety_oracles<U: WeightUnit>(
         block: Block<V>,
-        latest_msgs_honest: &LatestMsgsHonest<Self>,
+        latest_msgs_honest: &LatestMessagesHonest<Self>,
         equivocators: &HashSet<V>,
         safety_oracle_threshold: U,
         weights: &validator::Weights<V, U>,
@@ -180,7 +193,7 @@ impl<V: validator::ValidatorName> Block<V> {
             j: &Justification<Block<V>>,
             equivocators: &HashSet<V>,
         ) -> HashMap<V, Message<V>> {
-            LatestMsgsHonest::from_latest_msgs(&LatestMsgs::from(j), equivocators)
+            LatestMessagesHonest::from_latest_messages(&LatestMessages::from(j), equivocators)
                 .iter()
                 .map(|m| (m.sender().clone(), m.clone()))
                 .collect()
@@ -265,8 +278,10 @@ impl<V: validator::ValidatorName> Block<V> {
             .into_iter()
             .filter(|x| {
                 x.iter().fold(<U as Zero<U>>::ZERO, |acc, validator| {
-                    // FIXME: U::default() or <U ...>::Zero? or U::NAN
-                    acc + weights.weight(validator).unwrap_or(U::NAN)
+                    // A validator missing from the weight map contributes no weight,
+                    // not `U::NAN` -- the old fallback made this `>` comparison
+                    // ill-defined for any clique containing such a validator.
+                    acc + weights.weight(validator).unwrap_or(<U as Zero<U>>::ZERO)
                 }) > safety_oracle_threshold
             })
             .collect()
@@ -282,7 +297,7 @@ impl<V: validator::ValidatorName> Block<V> {
     /// blocks);
     /// * a HashMap mapping blocks to their senders.
     pub fn parse_blockchains(
-        latest_msgs: &LatestMsgsHonest<Self>,
+        latest_msgs: &LatestMessagesHonest<Self>,
     ) -> (
         BlocksChildrenMap<V>,
         GenesisBlocks<V>,
@@ -435,8 +450,17 @@ impl<V: validator::ValidatorName> Block<V> {
         })
     }
 
+    /// LMD-GHOST fork choice: treats each validator's latest honest message as a vote for
+    /// the block it estimates, builds the block tree from the justification DAG rooted at
+    /// its genesis block(s), and at each level follows the child carrying the greatest
+    /// accumulated validator weight (a block's weight is the weight of every validator
+    /// whose latest vote is that block or one of its descendants), tie-breaking
+    /// deterministically by the child's `Hash`. Validators with no vote in
+    /// `latest_msgs` contribute nothing; equivocators are already excluded since
+    /// `latest_msgs` is a `LatestMessagesHonest`. An empty vote set yields `Err(Error)`,
+    /// not a genesis block: with no votes there is no block tree to read a root from.
     pub fn ghost<U: WeightUnit>(
-        latest_msgs: &LatestMsgsHonest<Self>,
+        latest_msgs: &LatestMessagesHonest<Self>,
         validators_weights: &validator::Weights<V, U>,
     ) -> Result<Self, Error> {
         let (visited, genesis, latest_blocks) = Self::parse_blockchains(latest_msgs);
@@ -453,6 +477,40 @@ impl<V: validator::ValidatorName> Block<V> {
         .and_then(|(opt_block, ..)| opt_block)
         .ok_or(Error)
     }
+
+    /// Descriptively-named alias for [`ghost`](#method.ghost), for callers that want the
+    /// current canonical chain head directly rather than through [`Estimator::estimate`],
+    /// which wraps it in a new child block.
+    pub fn canonical_head<U: WeightUnit>(
+        latest_msgs: &LatestMessagesHonest<Self>,
+        validators_weights: &validator::Weights<V, U>,
+    ) -> Result<Self, Error> {
+        Block::ghost(latest_msgs, validators_weights)
+    }
+
+    /// Whether the current [`canonical_head`](#method.canonical_head) is attested by a
+    /// safety oracle: a clique of validators, collectively weighing more than
+    /// `safety_oracle_threshold`, each of whom has seen every other clique member agree on
+    /// this block. This is what turns `safety_oracles`' clique detection from a
+    /// fact-about-the-DAG into an actual finality decision for the block `ghost` is
+    /// currently picking, rather than requiring callers to run both and cross-reference
+    /// the result by hand.
+    pub fn canonical_head_is_safe<U: WeightUnit>(
+        latest_msgs: &LatestMessagesHonest<Self>,
+        equivocators: &HashSet<V>,
+        safety_oracle_threshold: U,
+        validators_weights: &validator::Weights<V, U>,
+    ) -> Result<bool, Error> {
+        let head = Block::ghost(latest_msgs, validators_weights)?;
+        Ok(!Block::safety_oracles(
+            head,
+            latest_msgs,
+            equivocators,
+            safety_oracle_threshold,
+            validators_weights,
+        )
+        .is_empty())
+    }
 }
 
 #[cfg(test)]
@@ -463,7 +521,7 @@ mod tests {
     use std::iter;
     use std::iter::FromIterator;
 
-    use crate::justification::{Justification, LatestMsgs, LatestMsgsHonest};
+    use crate::justification::{Justification, LatestMessages, LatestMessagesHonest};
     use crate::validator;
 
     #[test]
@@ -545,11 +603,11 @@ mod tests {
         // doesn't actually test anything useful. In fact, I suspect nothing in this file really
         // makes any sense since `Block<V>` doesn't carry any data whatsoever.
 
-        let mut latest_msgs = LatestMsgs::empty();
+        let mut latest_msgs = LatestMessages::empty();
         latest_msgs.update(&genesis);
         latest_msgs.update(&block_1);
         latest_msgs.update(&block_2);
-        let latest_msgs_honest = LatestMsgsHonest::from_latest_msgs(&latest_msgs, &HashSet::new());
+        let latest_msgs_honest = LatestMessagesHonest::from_latest_messages(&latest_msgs, &HashSet::new());
 
         let (children_map, genesis_set, _senders_map) =
             Block::parse_blockchains(&latest_msgs_honest);
@@ -589,6 +647,84 @@ mod tests {
         // );
     }
 
+    #[test]
+    fn canonical_head_matches_ghost() {
+        let validators_weights =
+            validator::Weights::new(vec![(0, 1.0), (1, 1.0)].into_iter().collect());
+
+        let genesis = Message::new(0, Justification::empty(), Block::new(None));
+        let mut justification = Justification::empty();
+        justification.insert(genesis.clone());
+        let block_1 = Message::new(
+            1,
+            justification,
+            Block::new(Some(genesis.estimate().clone())),
+        );
+
+        let mut latest_msgs = LatestMessages::empty();
+        latest_msgs.update(&genesis);
+        latest_msgs.update(&block_1);
+        let latest_msgs_honest = LatestMessagesHonest::from_latest_messages(&latest_msgs, &HashSet::new());
+
+        assert_eq!(
+            Block::canonical_head(&latest_msgs_honest, &validators_weights).unwrap(),
+            Block::ghost(&latest_msgs_honest, &validators_weights).unwrap(),
+        );
+    }
+
+    #[test]
+    fn canonical_head_is_safe_above_and_below_threshold() {
+        let validators: Vec<u32> = (0..2).collect();
+        let validators_weights =
+            validator::Weights::new(validators.iter().cloned().zip(iter::repeat(1.0)).collect());
+
+        let mut state = validator::State::new(
+            validators_weights.clone(),
+            0.0,
+            LatestMessages::empty(),
+            1.0,
+            HashSet::new(),
+        );
+
+        let proto_b0 = Block::from(ProtoBlock::new(None));
+        let m0 = Message::new(validators[0], Justification::empty(), proto_b0.clone());
+        state.update(&[&m0]);
+        let mut j1 = Justification::empty();
+        j1.insert(m0.clone());
+        let m1 = Message::new(validators[1], j1, proto_b0.clone());
+        state.update(&[&m1]);
+        let mut j2 = Justification::empty();
+        j2.insert(m1.clone());
+        let m2 = Message::new(validators[0], j2, proto_b0.clone());
+        state.update(&[&m2]);
+
+        // validators[0] has now seen validators[1] seeing validators[0] vote for
+        // `proto_b0`, so `proto_b0` is both the canonical head and attested by a clique
+        // weighing 2.0, crossing a threshold below that ...
+        let latest_msgs_honest =
+            LatestMessagesHonest::from_latest_messages(state.latests_messages(), state.equivocators());
+        assert_eq!(
+            Block::canonical_head(&latest_msgs_honest, &validators_weights).unwrap(),
+            proto_b0,
+        );
+        assert!(Block::canonical_head_is_safe(
+            &latest_msgs_honest,
+            state.equivocators(),
+            1.0,
+            &validators_weights,
+        )
+        .unwrap());
+
+        // ... but not one at or above the clique's full weight.
+        assert!(!Block::canonical_head_is_safe(
+            &latest_msgs_honest,
+            state.equivocators(),
+            2.0,
+            &validators_weights,
+        )
+        .unwrap());
+    }
+
     #[test]
     fn safety_oracles() {
         let nodes = 3;
@@ -600,7 +736,7 @@ mod tests {
         let mut state = validator::State::new(
             validators_weights.clone(),
             0.0,
-            LatestMsgs::empty(),
+            LatestMessages::empty(),
             1.0,
             HashSet::new(),
         );
@@ -623,7 +759,7 @@ mod tests {
         assert_eq!(
             Block::safety_oracles(
                 proto_b0.clone(),
-                &LatestMsgsHonest::from_latest_msgs(state.latests_msgs(), state.equivocators()),
+                &LatestMessagesHonest::from_latest_messages(state.latests_messages(), state.equivocators()),
                 state.equivocators(),
                 2.0,
                 &validators_weights
@@ -638,7 +774,7 @@ mod tests {
         assert_eq!(
             Block::safety_oracles(
                 proto_b0.clone(),
-                &LatestMsgsHonest::from_latest_msgs(state.latests_msgs(), state.equivocators()),
+                &LatestMessagesHonest::from_latest_messages(state.latests_messages(), state.equivocators()),
                 state.equivocators(),
                 1.0,
                 &validators_weights
@@ -660,7 +796,7 @@ mod tests {
         assert_eq!(
             Block::safety_oracles(
                 proto_b0.clone(),
-                &LatestMsgsHonest::from_latest_msgs(state.latests_msgs(), state.equivocators()),
+                &LatestMessagesHonest::from_latest_messages(state.latests_messages(), state.equivocators()),
                 state.equivocators(),
                 1.0,
                 &validators_weights
@@ -678,7 +814,7 @@ mod tests {
         assert_eq!(
             Block::safety_oracles(
                 proto_b0.clone(),
-                &LatestMsgsHonest::from_latest_msgs(state.latests_msgs(), state.equivocators()),
+                &LatestMessagesHonest::from_latest_messages(state.latests_messages(), state.equivocators()),
                 state.equivocators(),
                 1.0,
                 &validators_weights
@@ -693,7 +829,7 @@ mod tests {
         assert_eq!(
             Block::safety_oracles(
                 proto_b1,
-                &LatestMsgsHonest::from_latest_msgs(state.latests_msgs(), state.equivocators()),
+                &LatestMessagesHonest::from_latest_messages(state.latests_messages(), state.equivocators()),
                 state.equivocators(),
                 1.0,
                 &validators_weights
@@ -708,7 +844,7 @@ mod tests {
         assert_eq!(
             Block::safety_oracles(
                 proto_b2.clone(),
-                &LatestMsgsHonest::from_latest_msgs(state.latests_msgs(), state.equivocators()),
+                &LatestMessagesHonest::from_latest_messages(state.latests_messages(), state.equivocators()),
                 state.equivocators(),
                 1.0,
                 &validators_weights
@@ -732,7 +868,7 @@ mod tests {
         assert_eq!(
             Block::safety_oracles(
                 proto_b0,
-                &LatestMsgsHonest::from_latest_msgs(state.latests_msgs(), state.equivocators()),
+                &LatestMessagesHonest::from_latest_messages(state.latests_messages(), state.equivocators()),
                 state.equivocators(),
                 1.0,
                 &validators_weights
@@ -746,7 +882,7 @@ mod tests {
         assert_eq!(
             Block::safety_oracles(
                 proto_b2,
-                &LatestMsgsHonest::from_latest_msgs(state.latests_msgs(), state.equivocators()),
+                &LatestMessagesHonest::from_latest_messages(state.latests_messages(), state.equivocators()),
                 state.equivocators(),
                 1.0,
                 &validators_weights
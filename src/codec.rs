@@ -0,0 +1,196 @@
+use std::convert::TryInto;
+
+use crate::estimator::Estimator;
+use crate::justification::Justification;
+use crate::message::Message;
+use crate::util::hash::Hash;
+use crate::util::id::Id;
+
+/// Errors from decoding a [`WireCodec`] payload. Unlike a plain deserialization failure,
+/// these also cover payloads that decode structurally but fail a post-decode validity
+/// check (wrong field count, an id of the wrong width, ...) — `decode` re-verifies
+/// structure rather than trusting the bytes.
+///
+/// [`WireCodec`]: trait.WireCodec.html
+#[derive(Debug, Eq, PartialEq)]
+pub enum DecodeError {
+    UnexpectedEof,
+    LengthMismatch,
+    InvalidStructure(&'static str),
+    /// Bytes remained after decoding the expected structure -- accepting them would let
+    /// two different byte streams decode to the same value, defeating content addressing.
+    TrailingBytes,
+    /// A set that is supposed to be in canonical (ascending content-id) order wasn't --
+    /// accepting it would let the same logical set round-trip through more than one
+    /// valid encoding.
+    UnsortedSet,
+}
+
+/// Canonical, deterministic, length-prefixed (SCALE-style) binary encoding. This is a
+/// prerequisite for the signing and gossip subsystems and lets a consumer snapshot a
+/// validator's state to disk or send an individual message over a socket, rather than
+/// relying on `serde`'s non-canonical, implementation-defined layouts.
+///
+/// Every integer is fixed-width big-endian and every variable-length collection is
+/// length-prefixed with a fixed-width count, so there is exactly one valid encoding per
+/// value: no variable-width integer means no non-minimal encoding of the same length to
+/// reject, and [`Message::decode_fields`] separately rejects trailing bytes and
+/// out-of-order justification sets, the two remaining ways a decode could be ambiguous.
+///
+/// `Estimator` implementations that want wire support implement this for their estimate
+/// type (and their `ValidatorName`) the same way they implement `Data`; `u32` is provided
+/// here as the simplest built-in example, matching the validator name type the
+/// `example::integer` module uses.
+///
+/// [`Message::decode_fields`]: struct.Message.html#method.decode_fields
+pub trait WireCodec: Sized {
+    fn encode(&self) -> Vec<u8>;
+    fn decode(bytes: &[u8]) -> Result<Self, DecodeError>;
+}
+
+impl WireCodec for u32 {
+    fn encode(&self) -> Vec<u8> {
+        self.to_be_bytes().to_vec()
+    }
+
+    fn decode(bytes: &[u8]) -> Result<Self, DecodeError> {
+        bytes
+            .try_into()
+            .map(u32::from_be_bytes)
+            .map_err(|_| DecodeError::LengthMismatch)
+    }
+}
+
+/// `count` as a 4-byte big-endian length prefix, followed by each chunk in turn as its
+/// own 4-byte big-endian length prefix plus bytes.
+fn encode_len_prefixed(chunks: &[Vec<u8>]) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(&(chunks.len() as u32).to_be_bytes());
+    for chunk in chunks {
+        out.extend_from_slice(&(chunk.len() as u32).to_be_bytes());
+        out.extend_from_slice(chunk);
+    }
+    out
+}
+
+/// Inverse of `encode_len_prefixed`: returns each chunk as a borrowed slice plus the
+/// number of bytes consumed, failing if the buffer is too short for the lengths it
+/// declares. Does not by itself reject trailing bytes after the chunks it reads --
+/// callers sitting at the top of a decode (e.g. [`Message::decode_fields`]) must check
+/// the returned consumed-length against the full input themselves.
+///
+/// [`Message::decode_fields`]: struct.Message.html#method.decode_fields
+fn read_len_prefixed(bytes: &[u8]) -> Result<(Vec<&[u8]>, usize), DecodeError> {
+    if bytes.len() < 4 {
+        return Err(DecodeError::UnexpectedEof);
+    }
+    let count = u32::from_be_bytes(bytes[0..4].try_into().unwrap()) as usize;
+    let mut cursor = 4;
+    let mut chunks = Vec::with_capacity(count);
+    for _ in 0..count {
+        if bytes.len() < cursor + 4 {
+            return Err(DecodeError::UnexpectedEof);
+        }
+        let len = u32::from_be_bytes(bytes[cursor..cursor + 4].try_into().unwrap()) as usize;
+        cursor += 4;
+        if bytes.len() < cursor + len {
+            return Err(DecodeError::UnexpectedEof);
+        }
+        chunks.push(&bytes[cursor..cursor + len]);
+        cursor += len;
+    }
+    Ok((chunks, cursor))
+}
+
+impl<E> Message<E>
+where
+    E: Estimator + WireCodec,
+    E::ValidatorName: WireCodec,
+{
+    /// Canonical encoding of this message's own fields: sender, estimate, and
+    /// justification as a list of ancestor ids (not full messages — reassembling those
+    /// requires a `Store`; see [`decode_fields`]), sorted ascending by id so the same
+    /// justification set always encodes to the same bytes regardless of insertion order.
+    ///
+    /// [`decode_fields`]: #method.decode_fields
+    pub fn encode(&self) -> Vec<u8> {
+        let mut justification_ids: Vec<Hash> =
+            self.justification().iter().map(Message::id).collect();
+        justification_ids.sort_unstable();
+        let justification_ids: Vec<Vec<u8>> = justification_ids
+            .iter()
+            .map(|id| id.as_bytes().to_vec())
+            .collect();
+        encode_len_prefixed(&[
+            self.sender().encode(),
+            self.estimate().encode(),
+            encode_len_prefixed(&justification_ids),
+        ])
+    }
+
+    /// Decodes the flat `(sender, estimate, justification ids)` tuple [`encode`]
+    /// produces, re-validating structure rather than trusting the bytes: field count,
+    /// that every justification id is exactly the width of a `Hash`, that the
+    /// justification ids are in the same ascending order `encode` always produces, and
+    /// that no bytes remain once every field has been read. Any of these failing would
+    /// mean a second, different byte string decodes to the same value, which would break
+    /// content addressing. A message's justification is itself made of `Message`s, not
+    /// raw bytes, so reassembling a full `Message` additionally requires resolving these
+    /// ids against a `Store` (see `crate::store`) to recover the actual ancestor
+    /// messages.
+    ///
+    /// [`encode`]: #method.encode
+    pub fn decode_fields(bytes: &[u8]) -> Result<(E::ValidatorName, E, Vec<Hash>), DecodeError> {
+        let (fields, consumed) = read_len_prefixed(bytes)?;
+        if consumed != bytes.len() {
+            return Err(DecodeError::TrailingBytes);
+        }
+        if fields.len() != 3 {
+            return Err(DecodeError::InvalidStructure("expected 3 top-level fields"));
+        }
+
+        let sender = E::ValidatorName::decode(fields[0])?;
+        let estimate = E::decode(fields[1])?;
+
+        let (justification_id_chunks, consumed) = read_len_prefixed(fields[2])?;
+        if consumed != fields[2].len() {
+            return Err(DecodeError::TrailingBytes);
+        }
+        let justification_ids = justification_id_chunks
+            .into_iter()
+            .map(|chunk| {
+                if chunk.len() != std::mem::size_of::<Hash>() {
+                    Err(DecodeError::InvalidStructure(
+                        "justification id has the wrong width",
+                    ))
+                } else {
+                    Ok(Hash::from_slice(chunk))
+                }
+            })
+            .collect::<Result<Vec<Hash>, DecodeError>>()?;
+
+        if !justification_ids.windows(2).all(|pair| pair[0] < pair[1]) {
+            return Err(DecodeError::UnsortedSet);
+        }
+
+        Ok((sender, estimate, justification_ids))
+    }
+}
+
+impl<E> Justification<E>
+where
+    E: Estimator + WireCodec,
+    E::ValidatorName: WireCodec,
+{
+    /// Encodes this justification as the list of its direct messages' ids, sorted
+    /// ascending so the same justification set always encodes to the same bytes
+    /// regardless of insertion order. As with [`Message::decode_fields`], resolving
+    /// these back into `Message`s requires a `Store`.
+    ///
+    /// [`Message::decode_fields`]: struct.Message.html#method.decode_fields
+    pub fn encode_ids(&self) -> Vec<u8> {
+        let mut ids: Vec<Hash> = self.iter().map(Message::id).collect();
+        ids.sort_unstable();
+        encode_len_prefixed(&ids.iter().map(|id| id.as_bytes().to_vec()).collect::<Vec<_>>())
+    }
+}
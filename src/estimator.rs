@@ -0,0 +1,34 @@
+use crate::justification::LatestMessagesHonest;
+use crate::message::Message;
+use crate::util::weight::WeightUnit;
+use crate::validator::Weights;
+
+/// A value a validator can vote for and that its peers can independently converge on: the
+/// `E` in `Message<E>`/`validator::State<E, U>`. Folds a set of latest honest messages
+/// into a new value of the same type via [`estimate`], the one piece of the CBC Casper
+/// protocol that is specific to what is actually being agreed on (an integer, a block, a
+/// boolean, ...).
+///
+/// The supertrait bounds here are not incidental: `Message<E>`/`Justification<E>` derive
+/// `Clone`/`Eq`/`Debug` and implement `Serialize` generically over `E` alone (no extra
+/// bounds repeated at each call site), so every one of those needs to already hold for
+/// `E` itself.
+///
+/// [`estimate`]: #tymethod.estimate
+pub trait Estimator: Clone + Eq + std::fmt::Debug + serde::Serialize + Sized {
+    /// How this estimator names the validators voting on it.
+    type ValidatorName: crate::validator::ValidatorName;
+
+    /// The error [`estimate`] fails with, e.g. when there are no latest messages to fold.
+    ///
+    /// [`estimate`]: #tymethod.estimate
+    type Error: std::error::Error;
+
+    /// Folds `latest_msgs` (weighted by `validators_weights`) into a new estimate. Two
+    /// validators presented with the same latest messages and weights must agree on the
+    /// same result, so implementations must not depend on message iteration order.
+    fn estimate<U: WeightUnit>(
+        latest_msgs: &LatestMessagesHonest<Message<Self>>,
+        validators_weights: &Weights<Self::ValidatorName, U>,
+    ) -> Result<Self, Self::Error>;
+}
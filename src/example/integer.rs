@@ -3,39 +3,115 @@ use message::{CasperMsg, Message};
 use justification::{LatestMsgs};
 use senders_weight::{SendersWeight};
 use weight_unit::{WeightUnit};
-use std::collections::HashSet;
+use std::collections::{BTreeSet, HashSet};
 use std::iter::FromIterator;
 type Validator = u32;
 
 pub type IntegerMsg = Message<u32 /*Estimate*/, Validator /*Sender*/>;
 
+/// A transaction spending `key`. Two transactions that spend the same `key` conflict
+/// (one is a double-spend of the other), regardless of `weight`.
 #[derive(Clone, Eq, Debug, Ord, PartialOrd, PartialEq, Hash)]
-pub struct Tx;
+pub struct Tx {
+    pub key: u64,
+    pub weight: u32,
+}
 
 impl Data for u32 {
     type Data = Self;
-    fn is_valid(_data: &Self::Data) -> bool {
-        true // FIXME
+    type Context = ();
+    fn is_valid(_data: &Self::Data, _context: &Self::Context) -> bool {
+        true // the echo-integer example carries no transactions to validate
     }
 }
 
-impl Estimate for u32 {
-    type M = IntegerMsg;
-    fn mk_estimate(
-        latest_msgs: &LatestMsgs<Self::M>,
-        _finalized_msg: Option<&Self::M>,
-        senders_weights: &SendersWeight<
-            <<Self as Estimate>::M as CasperMsg>::Sender,
-        >,
-        // in fact i could put the whole mempool inside of this proto_block and
-        // search for a reasonable set of txs in this function that does not
-        // conflict with the past blocks
-        _proto_block: Option<<Self as Data>::Data>,
-    ) -> Self {
+/// Per-block budget on the total `Tx::weight` a `TxBlock` may include. Keeps blocks from
+/// growing unbounded regardless of how large the mempool is.
+pub const BLOCK_WEIGHT_BUDGET: u32 = 1_000;
+
+/// A block of mutually non-conflicting transactions, built from a mempool rather than
+/// echoing back a bare integer. Ordered by its transaction keys so it has a canonical,
+/// deterministic representation (and therefore `Hash`/`Ord`) regardless of insertion
+/// order.
+#[derive(Clone, Eq, PartialEq, PartialOrd, Ord, Debug, Hash)]
+pub struct TxBlock(BTreeSet<Tx>);
+
+pub type TxBlockMsg = Message<TxBlock, Validator>;
+
+impl TxBlock {
+    pub fn txs(&self) -> &BTreeSet<Tx> {
+        &self.0
+    }
+
+    /// Collects every spend key spent by a transaction in any honest validator's latest
+    /// block in `latest_msgs`. Only those latest blocks are read: earlier blocks in their
+    /// justifications are not walked, so a key spent only there is not included. Used to
+    /// reject double-spends both when validating a single transaction and when
+    /// assembling a new block.
+    fn already_spent(
+        latest_msgs: &LatestMsgs<TxBlockMsg>,
+        equivocators: &HashSet<Validator>,
+    ) -> HashSet<u64> {
+        latest_msgs
+            .iter()
+            .filter(|(sender, _)| !equivocators.contains(sender))
+            .flat_map(|(_, msgs)| msgs.iter())
+            .flat_map(|msg| msg.get_estimate().txs().iter())
+            .map(|tx| tx.key)
+            .collect()
+    }
+}
+
+impl Data for TxBlock {
+    type Data = Tx;
+    /// The set of spend keys already committed in the honest validators' latest blocks
+    /// (see `TxBlock::already_spent`); earlier blocks are not included.
+    type Context = HashSet<u64>;
+
+    fn is_valid(data: &Self::Data, already_spent: &Self::Context) -> bool {
+        !already_spent.contains(&data.key)
+    }
+}
+
+/// A pluggable fork-choice rule consuming the same `LatestMsgs` + `SendersWeight` inputs
+/// `Estimate::mk_estimate` has always received, so alternative consensus rules (a
+/// GHOST-style heaviest-subtree choice, a Phragmén-style balanced selection, ...) can be
+/// developed and swapped in without editing `Estimate for u32` itself.
+pub trait EstimatorProvider {
+    /// Provider-specific tuning knobs; `()` for providers that need none.
+    type Config: Default;
+
+    fn estimate(
+        latest_msgs: &LatestMsgs<IntegerMsg>,
+        senders_weights: &SendersWeight<Validator>,
+        config: &Self::Config,
+    ) -> u32;
+}
+
+/// The fork-choice rule this crate has always used: sort the latest honest messages by
+/// sender weight and take the one whose cumulative weight first crosses 50%.
+pub struct WeightedMedian;
+
+impl EstimatorProvider for WeightedMedian {
+    type Config = ();
+
+    fn estimate(
+        latest_msgs: &LatestMsgs<IntegerMsg>,
+        senders_weights: &SendersWeight<Validator>,
+        _config: &Self::Config,
+    ) -> u32 {
+        // Equivocators get excluded from the fold entirely so a double-voting validator
+        // cannot bias the weighted median with more than one of its conflicting messages.
+        let (equivocators, _fault_weight) = latest_msgs.detect_equivocators(senders_weights);
+
         let mut msgs_sorted_by_estimate = Vec::from_iter(latest_msgs.iter().fold(
             HashSet::new(),
-            |latest, (_, latest_from_validator)| {
-                latest.union(&latest_from_validator).cloned().collect()
+            |latest, (sender, latest_from_validator)| {
+                if equivocators.contains(sender) {
+                    latest
+                } else {
+                    latest.union(&latest_from_validator).cloned().collect()
+                }
             },
         ));
         msgs_sorted_by_estimate.sort_unstable_by(|a, b| {
@@ -65,3 +141,97 @@ impl Estimate for u32 {
         *current_msg.next().unwrap().get_estimate()
     }
 }
+
+/// Selects which `EstimatorProvider` a validator instance runs, chosen once at
+/// construction time instead of being hard-coded into `Estimate for u32`. New providers
+/// register themselves here as a variant.
+pub enum EstimatorRegistry {
+    WeightedMedian(<WeightedMedian as EstimatorProvider>::Config),
+}
+
+impl Default for EstimatorRegistry {
+    fn default() -> Self {
+        EstimatorRegistry::WeightedMedian(Default::default())
+    }
+}
+
+impl EstimatorRegistry {
+    fn estimate(
+        &self,
+        latest_msgs: &LatestMsgs<IntegerMsg>,
+        senders_weights: &SendersWeight<Validator>,
+    ) -> u32 {
+        match self {
+            EstimatorRegistry::WeightedMedian(config) => {
+                WeightedMedian::estimate(latest_msgs, senders_weights, config)
+            }
+        }
+    }
+}
+
+impl Estimate for TxBlock {
+    type M = TxBlockMsg;
+
+    fn mk_estimate(
+        latest_msgs: &LatestMsgs<Self::M>,
+        _finalized_msg: Option<&Self::M>,
+        senders_weights: &SendersWeight<
+            <<Self as Estimate>::M as CasperMsg>::Sender,
+        >,
+        // The mempool of pending, not-yet-included transactions to choose a
+        // non-conflicting subset from, bounded by `BLOCK_WEIGHT_BUDGET`. Unlike
+        // `Data::is_valid`, which judges one transaction at a time, the mempool itself
+        // is not a single `Data::Data`, so it is threaded through as its own type.
+        proto_block: Option<HashSet<Tx>>,
+    ) -> Self {
+        let (equivocators, _fault_weight) = latest_msgs.detect_equivocators(senders_weights);
+        let already_spent = Self::already_spent(latest_msgs, &equivocators.iter().cloned().collect());
+
+        // Deterministic selection order: lowest key first, so every honest validator
+        // seeing the same mempool picks the same block.
+        let mut pending: Vec<Tx> = proto_block.into_iter().flatten().collect();
+        pending.sort_unstable_by_key(|tx| tx.key);
+
+        let mut chosen = BTreeSet::new();
+        let mut spent_keys = already_spent;
+        let mut used_weight: u32 = 0;
+        for tx in pending {
+            if spent_keys.contains(&tx.key) {
+                continue; // double-spend or already included upstream
+            }
+            if !Self::is_valid(&tx, &spent_keys) {
+                continue;
+            }
+            let Some(next_weight) = used_weight.checked_add(tx.weight) else {
+                continue;
+            };
+            if next_weight > BLOCK_WEIGHT_BUDGET {
+                continue;
+            }
+            used_weight = next_weight;
+            spent_keys.insert(tx.key);
+            chosen.insert(tx);
+        }
+        TxBlock(chosen)
+    }
+}
+
+impl Estimate for u32 {
+    type M = IntegerMsg;
+    fn mk_estimate(
+        latest_msgs: &LatestMsgs<Self::M>,
+        // Finalization no longer needs to be handled here: once a message finalizes,
+        // callers should run `LegacySenderState::prune` with the desired `LegacyPruningMode` so the
+        // justification DAG stays bounded without changing how an estimate is computed.
+        _finalized_msg: Option<&Self::M>,
+        senders_weights: &SendersWeight<
+            <<Self as Estimate>::M as CasperMsg>::Sender,
+        >,
+        // in fact i could put the whole mempool inside of this proto_block and
+        // search for a reasonable set of txs in this function that does not
+        // conflict with the past blocks
+        _proto_block: Option<<Self as Data>::Data>,
+    ) -> Self {
+        EstimatorRegistry::default().estimate(latest_msgs, senders_weights)
+    }
+}
@@ -7,22 +7,31 @@ use crate::message;
 use traits::{Estimate, Zero};
 use util::weight::{SendersWeight, WeightUnit};
 
+// Everything in this module down to the new-world section near the bottom predates
+// `crate::message::Message`/`crate::validator::State` and is bound against
+// `message::Trait`, which nothing in this crate implements -- the `Legacy` prefix on
+// every name here documents that it is dead code kept around for its own design history,
+// not a parallel justification scheme still in service. The types a `validator::State`
+// actually uses -- `LatestMessages`, `LatestMessagesHonest`, `Justification<E:
+// Estimator>` -- live in the new-world section below, generic over `Message<E>` via the
+// `JustifiedMessage` bridging trait rather than over `message::Trait`.
+//
 /// Struct that holds the set of the message::Traits that justify
 /// the current message
 /// Works like a Vec
 #[derive(Eq, PartialEq, Clone, Default, Hash)]
-pub struct Justification<M: message::Trait>(Vec<M>);
+pub struct LegacyJustification<M: message::Trait>(Vec<M>);
 
-impl<M: message::Trait> Justification<M> {
+impl<M: message::Trait> LegacyJustification<M> {
     /// Re-exports from Vec wrapping M
     pub fn new() -> Self {
-        Justification(Vec::new())
+        LegacyJustification(Vec::new())
     }
 
-    /// creates a new Justification instance from a Vec of message::Trait
-    /// and a SenderState
-    pub fn from_msgs(msgs: Vec<M>, sender_state: &SenderState<M>) -> (Self, SenderState<M>) {
-        let mut j = Justification::new();
+    /// creates a new LegacyJustification instance from a Vec of message::Trait
+    /// and a LegacySenderState
+    pub fn from_msgs(msgs: Vec<M>, sender_state: &LegacySenderState<M>) -> (Self, LegacySenderState<M>) {
+        let mut j = LegacyJustification::new();
         let msgs: HashSet<_> = msgs.iter().collect();
         let (_, sender_state) = j.faulty_inserts(msgs, sender_state);
         (j, sender_state)
@@ -62,21 +71,21 @@ impl<M: message::Trait> Justification<M> {
         senders_weights: &SendersWeight<<M as message::Trait>::Sender>,
         // data: Option<<<M as message::Trait>::Estimate as Data>::Data>,
     ) -> Result<M::Estimate, &'static str> {
-        let latest_msgs = LatestMsgs::from(self);
-        let latest_msgs_honest = LatestMsgsHonest::from_latest_msgs(&latest_msgs, equivocators);
+        let latest_msgs = LegacyLatestMsgs::from(self);
+        let latest_msgs_honest = LegacyLatestMsgsHonest::from_latest_msgs(&latest_msgs, equivocators);
         M::Estimate::mk_estimate(&latest_msgs_honest, senders_weights)
     }
 
     // Custom functions
 
-    /// insert msgs to the Justification, accepting up to $thr$ faults by
+    /// insert msgs to the LegacyJustification, accepting up to $thr$ faults by
     /// weight, returns success=true if at least one msg of the set gets
     /// successfully included in the justification
     pub fn faulty_inserts(
         &mut self,
         msgs: HashSet<&M>,
-        sender_state: &SenderState<M>,
-    ) -> (bool, SenderState<M>) {
+        sender_state: &LegacySenderState<M>,
+    ) -> (bool, LegacySenderState<M>) {
         let msgs = sender_state.sort_by_faultweight(msgs);
         // do the actual insertions to the state
         msgs.iter().fold(
@@ -94,10 +103,11 @@ impl<M: message::Trait> Justification<M> {
     pub fn faulty_insert(
         &mut self,
         msg: &M,
-        sender_state: &SenderState<M>,
-    ) -> (bool, SenderState<M>) {
+        sender_state: &LegacySenderState<M>,
+    ) -> (bool, LegacySenderState<M>) {
         let mut sender_state = sender_state.clone();
-        let is_equivocation = sender_state.latest_msgs.equivocate(msg);
+        let conflict = sender_state.latest_msgs.find_conflict(msg);
+        let is_equivocation = conflict.is_some();
 
         let sender = msg.sender();
         let sender_weight = sender_state
@@ -115,6 +125,19 @@ impl<M: message::Trait> Justification<M> {
                 let success = self.insert(msg.clone());
                 if success {
                     sender_state.latest_msgs.update(msg);
+                    // equivocators keep a zero balance in the weight cache, so only a
+                    // non-equivocating vote updates it
+                    if let Some(conflicting_msg) = conflict {
+                        sender_state.equivocation_evidence.push(LegacyEquivocationEvidence {
+                            sender: sender.clone(),
+                            msg_a: msg.clone(),
+                            msg_b: conflicting_msg,
+                        });
+                    } else if !already_in_equivocators {
+                        sender_state
+                            .weight_cache
+                            .update(sender, msg, sender_weight);
+                    }
                 }
                 (success, sender_state)
             }
@@ -125,6 +148,17 @@ impl<M: message::Trait> Justification<M> {
                     let success = self.insert(msg.clone());
                     if success {
                         sender_state.latest_msgs.update(msg);
+                        if let Some(conflicting_msg) = conflict {
+                            sender_state.equivocation_evidence.push(LegacyEquivocationEvidence {
+                                sender: sender.clone(),
+                                msg_a: msg.clone(),
+                                msg_b: conflicting_msg,
+                            });
+                        }
+                        // a newly listed equivocator's balance drops to zero
+                        sender_state
+                            .weight_cache
+                            .update(sender, msg, WeightUnit::ZERO);
                         if sender_state.equivocators.insert(sender.clone()) {
                             sender_state.state_fault_weight += sender_weight;
                         }
@@ -138,13 +172,13 @@ impl<M: message::Trait> Justification<M> {
     }
 
     /// this function sets the weight of the equivocator to zero right away
-    /// (returned on SenderState) and add his message to the state, since now his
+    /// (returned on LegacySenderState) and add his message to the state, since now his
     /// equivocation doesnt count to the state fault weight anymore
     pub fn faulty_insert_with_slash(
         &mut self,
         msg: &M,
-        mut sender_state: SenderState<M>,
-    ) -> (bool, SenderState<M>) {
+        mut sender_state: LegacySenderState<M>,
+    ) -> (bool, LegacySenderState<M>) {
         let is_equivocation = sender_state.latest_msgs.equivocate(msg);
         if is_equivocation {
             let sender = msg.sender();
@@ -159,19 +193,19 @@ impl<M: message::Trait> Justification<M> {
     }
 }
 
-impl<M: message::Trait> Debug for Justification<M> {
+impl<M: message::Trait> Debug for LegacyJustification<M> {
     fn fmt(&self, f: &mut Formatter) -> ::std::fmt::Result {
         write!(f, "{:?}", self.0)
     }
 }
 
 /// Set of latest honest messages
-pub struct LatestMsgsHonest<M: message::Trait>(HashSet<M>);
+pub struct LegacyLatestMsgsHonest<M: message::Trait>(HashSet<M>);
 
-impl<M: message::Trait> LatestMsgsHonest<M> {
+impl<M: message::Trait> LegacyLatestMsgsHonest<M> {
     /// Create an empty set
     fn new() -> Self {
-        LatestMsgsHonest(HashSet::new())
+        LegacyLatestMsgsHonest(HashSet::new())
     }
 
     /// Insert message to the set
@@ -181,7 +215,7 @@ impl<M: message::Trait> LatestMsgsHonest<M> {
 
     /// Filters the latest messages
     pub fn from_latest_msgs(
-        latest_msgs: &LatestMsgs<M>,
+        latest_msgs: &LegacyLatestMsgs<M>,
         equivocators: &HashSet<M::Sender>,
     ) -> Self {
         latest_msgs
@@ -193,7 +227,7 @@ impl<M: message::Trait> LatestMsgsHonest<M> {
                     msgs.iter().next()
                 }
             })
-            .fold(LatestMsgsHonest::new(), |mut acc, msg| {
+            .fold(LegacyLatestMsgsHonest::new(), |mut acc, msg| {
                 acc.insert(msg.clone());
                 acc
             })
@@ -219,12 +253,12 @@ impl<M: message::Trait> LatestMsgsHonest<M> {
 /// Latest messages from a sender are all their messages that are not
 /// in the dependency of another of their messages
 #[derive(Eq, PartialEq, Clone, Default, Debug)]
-pub struct LatestMsgs<M: message::Trait>(HashMap<<M as message::Trait>::Sender, HashSet<M>>);
+pub struct LegacyLatestMsgs<M: message::Trait>(HashMap<<M as message::Trait>::Sender, HashSet<M>>);
 
-impl<M: message::Trait> LatestMsgs<M> {
+impl<M: message::Trait> LegacyLatestMsgs<M> {
     /// Create an empty map
     pub fn new() -> Self {
-        LatestMsgs(HashMap::new())
+        LegacyLatestMsgs(HashMap::new())
     }
 
     /// insert a new set of messages for a sender
@@ -303,16 +337,52 @@ impl<M: message::Trait> LatestMsgs<M> {
 
     /// checks whether msg_new equivocates with latest msgs
     fn equivocate(&self, msg_new: &M) -> bool {
+        self.find_conflict(msg_new).is_some()
+    }
+
+    /// The existing latest message from `msg_new`'s sender that `msg_new` equivocates
+    /// with, if any, so the caller can keep the actual conflicting pair rather than just
+    /// a yes/no answer.
+    fn find_conflict(&self, msg_new: &M) -> Option<M> {
         self.get(msg_new.sender())
-            .map(|latest_msgs| latest_msgs.iter().any(|m| m.equivocates(&msg_new)))
-            .unwrap_or(false)
+            .and_then(|latest_msgs| latest_msgs.iter().find(|m| m.equivocates(&msg_new)).cloned())
+    }
+
+    /// Detects senders that are currently equivocating, and their summed fault weight as
+    /// tracked by `senders_weights`.
+    ///
+    /// `update` only ever keeps pairwise causally-incomparable tips for a given sender (a
+    /// new message either replaces a dependent tip, is dropped as an ancestor of an
+    /// existing tip, or is added alongside existing tips it is independent from), so a
+    /// sender holding more than one latest message is already equivocating. This walks
+    /// the map re-confirming that invariant with `depends` rather than trusting set size
+    /// alone, and sums the weight of every sender caught this way.
+    pub fn detect_equivocators(
+        &self,
+        senders_weights: &SendersWeight<M::Sender>,
+    ) -> (HashSet<M::Sender>, WeightUnit) {
+        self.0.iter().fold(
+            (HashSet::new(), WeightUnit::ZERO),
+            |(mut equivocators, mut fault_weight), (sender, msgs)| {
+                let is_equivocating = msgs.len() > 1
+                    && msgs.iter().all(|m| {
+                        msgs.iter()
+                            .filter(|&n| n != m)
+                            .all(|n| !m.depends(n) && !n.depends(m))
+                    });
+                if is_equivocating && equivocators.insert(sender.clone()) {
+                    fault_weight += senders_weights.weight(sender).unwrap_or(WeightUnit::ZERO);
+                }
+                (equivocators, fault_weight)
+            },
+        )
     }
 }
 
-impl<'z, M: message::Trait> From<&'z Justification<M>> for LatestMsgs<M> {
+impl<'z, M: message::Trait> From<&'z LegacyJustification<M>> for LegacyLatestMsgs<M> {
     /// extract the latest messages from a justification
-    fn from(j: &Justification<M>) -> Self {
-        let mut latest_msgs: LatestMsgs<M> = LatestMsgs::new();
+    fn from(j: &LegacyJustification<M>) -> Self {
+        let mut latest_msgs: LegacyLatestMsgs<M> = LegacyLatestMsgs::new();
         let mut queue: VecDeque<M> = j.iter().cloned().collect();
         while let Some(msg) = queue.pop_front() {
             if latest_msgs.update(&msg) {
@@ -324,24 +394,184 @@ impl<'z, M: message::Trait> From<&'z Justification<M>> for LatestMsgs<M> {
         latest_msgs
     }
 }
-// impl<'z, M: message::Trait> From<&'z Justification<M>> for LatestMsgs<M> {
-//     fn from(j: &Justification<M>) -> Self {
+// impl<'z, M: message::Trait> From<&'z LegacyJustification<M>> for LegacyLatestMsgs<M> {
+//     fn from(j: &LegacyJustification<M>) -> Self {
 //         fn recur_func<M: message::Trait>(
-//             j: &Justification<M>,
-//             latest_msgs: LatestMsgs<M>,
-//         ) -> LatestMsgs<M> {
+//             j: &LegacyJustification<M>,
+//             latest_msgs: LegacyLatestMsgs<M>,
+//         ) -> LegacyLatestMsgs<M> {
 //             j.iter().fold(latest_msgs, |mut acc, m| {
 //                 acc.update(m);
 //                 recur_func(m.justification(), acc)
 //             })
 //         }
-//         recur_func(j, LatestMsgs::new())
+//         recur_func(j, LegacyLatestMsgs::new())
 //     }
 // }
 
+/// Incrementally-maintained, proto-array-style cache of accumulated validator weight per
+/// message. `LegacyJustification::mk_estimate` rebuilds `LegacyLatestMsgs` from scratch via
+/// `LegacyLatestMsgs::from(&LegacyJustification)`, a BFS over the entire justification DAG, every
+/// time it's called; `LegacySenderState` already maintains an up-to-date `LegacyLatestMsgs`
+/// incrementally via `update()`, so `LegacySenderState::cached_estimate` reads that directly
+/// instead. This cache additionally tracks, per message, the total weight of every
+/// sender whose current latest message has it as a (possibly indirect) justification
+/// ancestor, updated by `update()` applying only the signed delta from a sender's vote
+/// changing rather than re-walking the whole DAG, for fork-choice-style consumers that
+/// want a message's accumulated weight without re-deriving it.
+#[derive(Debug, Clone, Default)]
+pub struct LegacyWeightCache<M: message::Trait> {
+    weight: HashMap<M, WeightUnit>,
+    balances: HashMap<M::Sender, (M, WeightUnit)>,
+}
+
+impl<M: message::Trait> LegacyWeightCache<M> {
+    pub fn new() -> Self {
+        LegacyWeightCache {
+            weight: HashMap::new(),
+            balances: HashMap::new(),
+        }
+    }
+
+    /// Applies the weight delta from `sender`'s latest message changing to `new_msg`:
+    /// subtracts `sender`'s previous contribution from its old message's ancestors (if
+    /// any), then adds `sender_weight` to `new_msg`'s ancestors, visiting each message at
+    /// most once per walk so a diamond in the justification DAG isn't double counted.
+    pub fn update(&mut self, sender: &M::Sender, new_msg: &M, sender_weight: WeightUnit) {
+        if let Some((old_msg, old_weight)) = self.balances.get(sender).cloned() {
+            Self::apply_delta(&mut self.weight, &old_msg, -old_weight);
+        }
+        Self::apply_delta(&mut self.weight, new_msg, sender_weight);
+        self.balances
+            .insert(sender.clone(), (new_msg.clone(), sender_weight));
+    }
+
+    fn apply_delta(weight: &mut HashMap<M, WeightUnit>, msg: &M, delta: WeightUnit) {
+        let mut queue: VecDeque<M> = VecDeque::new();
+        let mut seen: HashSet<M> = HashSet::new();
+        queue.push_back(msg.clone());
+        seen.insert(msg.clone());
+        while let Some(m) = queue.pop_front() {
+            *weight.entry(m.clone()).or_insert(WeightUnit::ZERO) += delta;
+            for parent in m.justification().iter() {
+                if seen.insert(parent.clone()) {
+                    queue.push_back(parent.clone());
+                }
+            }
+        }
+    }
+
+    /// Accumulated weight attributed to `msg` by the most recent `update` calls, without
+    /// re-walking the DAG.
+    pub fn weight_of(&self, msg: &M) -> WeightUnit {
+        self.weight.get(msg).copied().unwrap_or(WeightUnit::ZERO)
+    }
+}
+
+/// Controls how much justification history `LegacySenderState::prune` discards once a message
+/// has been finalized, so a long-running validator can bound its memory use instead of
+/// retaining the entire history forever.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LegacyPruningMode {
+    /// Keep every message forever; `prune` becomes a no-op.
+    Archive,
+    /// Discard every message that is a (transitive) ancestor of the finalized message,
+    /// keeping only the frontier needed for future estimates.
+    PruneBelowFinalized,
+    /// Like `PruneBelowFinalized`, but keeps the last `n` rounds of justification below
+    /// the finalized message instead of discarding everything below it.
+    KeepLastRounds(usize),
+}
+
+/// A verifiable pair of conflicting messages from the same sender, produced whenever
+/// `LegacyJustification::faulty_insert`/`faulty_insert_with_slash` detect an equivocation, so a
+/// receiver can broadcast or act on the misbehavior (e.g. an on-chain slashing module)
+/// without re-deriving it from the raw justification DAG itself.
+#[derive(Debug, Clone)]
+pub struct LegacyEquivocationEvidence<M: message::Trait> {
+    sender: M::Sender,
+    msg_a: M,
+    msg_b: M,
+}
+
+impl<M: message::Trait> LegacyEquivocationEvidence<M> {
+    pub fn sender(&self) -> &M::Sender {
+        &self.sender
+    }
+
+    pub fn messages(&self) -> (&M, &M) {
+        (&self.msg_a, &self.msg_b)
+    }
+
+    /// Independently re-checks that both messages are from `sender` and mutually
+    /// non-dependent, so a receiver can validate a slashing claim without trusting the
+    /// reporter.
+    pub fn verify(&self, senders_weights: &SendersWeight<M::Sender>) -> bool {
+        senders_weights.weight(&self.sender).is_ok()
+            && self.msg_a.sender() == &self.sender
+            && self.msg_b.sender() == &self.sender
+            && self.msg_a != self.msg_b
+            && !self.msg_a.depends(&self.msg_b)
+            && !self.msg_b.depends(&self.msg_a)
+    }
+}
+
+/// Reference-counted garbage collection layered on top of `LegacySenderState::prune`. A pruned
+/// message may still be directly referenced from another retained message's
+/// justification, so rather than forgetting it outright, its reference count is tracked
+/// and it is only downgraded once nothing retained points to it anymore. A downgraded
+/// message keeps a lightweight `(sender, estimate)` summary rather than being dropped
+/// entirely, so lookups that only need "who sent this and what did they claim" (e.g.
+/// evidence or slashing checks) keep working; reconstructing `depends`/`equivocates`
+/// against a summarized ancestor still needs the full message and is out of scope here.
+#[derive(Debug, Clone, Default)]
+pub struct LegacyGarbageCollector<M: message::Trait> {
+    refcounts: HashMap<M, usize>,
+    summaries: HashMap<M, (M::Sender, M::Estimate)>,
+}
+
+impl<M: message::Trait> LegacyGarbageCollector<M> {
+    pub fn new() -> Self {
+        LegacyGarbageCollector {
+            refcounts: HashMap::new(),
+            summaries: HashMap::new(),
+        }
+    }
+
+    /// Registers that `msg` is still directly referenced by a retained message, keeping
+    /// it (or its summary) alive.
+    fn retain(&mut self, msg: &M) {
+        *self.refcounts.entry(msg.clone()).or_insert(0) += 1;
+    }
+
+    /// Drops the one reference a just-pruned message had; once nothing retained
+    /// references it anymore, it is downgraded to a summary.
+    fn release(&mut self, msg: &M) {
+        if let Some(count) = self.refcounts.get_mut(msg) {
+            *count = count.saturating_sub(1);
+            if *count > 0 {
+                return;
+            }
+        }
+        self.refcounts.remove(msg);
+        self.summaries
+            .insert(msg.clone(), (msg.sender().clone(), msg.estimate().clone()));
+    }
+
+    /// The `(sender, estimate)` a pruned message was summarized to, if it has been
+    /// garbage collected.
+    pub fn summary_of(&self, msg: &M) -> Option<(&M::Sender, &M::Estimate)> {
+        self.summaries.get(msg).map(|(sender, estimate)| (sender, estimate))
+    }
+
+    pub fn is_collected(&self, msg: &M) -> bool {
+        self.summaries.contains_key(msg)
+    }
+}
+
 /// struct that stores the inner state of the sender
 #[derive(Debug, Clone)]
-pub struct SenderState<M: message::Trait> {
+pub struct LegacySenderState<M: message::Trait> {
     /// current state total fault weight
     state_fault_weight: WeightUnit,
     /// fault tolerance threshold
@@ -351,29 +581,46 @@ pub struct SenderState<M: message::Trait> {
     /// this sender's last message
     /// TODO: better name?
     my_last_msg: Option<M>,
-    latest_msgs: LatestMsgs<M>,
+    latest_msgs: LegacyLatestMsgs<M>,
     equivocators: HashSet<M::Sender>,
+    /// Incremental fork-choice weight cache, kept current by `faulty_insert`. See
+    /// [`LegacyWeightCache`].
+    weight_cache: LegacyWeightCache<M>,
+    /// Reference-counted bookkeeping for messages `prune` has discarded from
+    /// `latest_msgs`. See [`LegacyGarbageCollector`].
+    gc: LegacyGarbageCollector<M>,
+    /// Conflicting message pairs collected by `faulty_insert`/`faulty_insert_with_slash`.
+    equivocation_evidence: Vec<LegacyEquivocationEvidence<M>>,
 }
 
-impl<M: message::Trait> SenderState<M> {
+impl<M: message::Trait> LegacySenderState<M> {
     pub fn new(
         senders_weights: SendersWeight<M::Sender>,
         state_fault_weight: WeightUnit,
         my_last_msg: Option<M>,
-        latest_msgs: LatestMsgs<M>,
+        latest_msgs: LegacyLatestMsgs<M>,
         thr: WeightUnit,
         equivocators: HashSet<M::Sender>,
     ) -> Self {
-        SenderState {
+        LegacySenderState {
             senders_weights,
             equivocators,
             state_fault_weight,
             thr,
             my_last_msg,
             latest_msgs,
+            weight_cache: LegacyWeightCache::new(),
+            gc: LegacyGarbageCollector::new(),
+            equivocation_evidence: Vec::new(),
         }
     }
 
+    /// Conflicting message pairs collected so far by `faulty_insert`/
+    /// `faulty_insert_with_slash`, one per equivocation detected.
+    pub fn equivocation_evidence(&self) -> &[LegacyEquivocationEvidence<M>] {
+        &self.equivocation_evidence
+    }
+
     pub fn equivocators(&self) -> &HashSet<M::Sender> {
         &self.equivocators
     }
@@ -390,11 +637,11 @@ impl<M: message::Trait> SenderState<M> {
         &self.my_last_msg
     }
 
-    pub fn latests_msgs(&self) -> &LatestMsgs<M> {
+    pub fn latests_msgs(&self) -> &LegacyLatestMsgs<M> {
         &self.latest_msgs
     }
 
-    pub fn latests_msgs_as_mut(&mut self) -> &mut LatestMsgs<M> {
+    pub fn latests_msgs_as_mut(&mut self) -> &mut LegacyLatestMsgs<M> {
         &mut self.latest_msgs
     }
 
@@ -402,6 +649,16 @@ impl<M: message::Trait> SenderState<M> {
         self.state_fault_weight
     }
 
+    /// Threshold `t` below which accumulated equivocation weight is tolerated.
+    pub fn threshold(&self) -> WeightUnit {
+        self.thr
+    }
+
+    /// Whether the fault weight accumulated so far stays within the tolerated threshold.
+    pub fn within_fault_tolerance(&self) -> bool {
+        self.state_fault_weight <= self.thr
+    }
+
     pub fn set_fault_weight(&mut self, fault_weight: WeightUnit) {
         self.state_fault_weight = fault_weight
     }
@@ -410,6 +667,99 @@ impl<M: message::Trait> SenderState<M> {
         self.thr = thresh
     }
 
+    /// Discards justification history made obsolete by `finalized_msg`, according to
+    /// `mode`. Safe to call incrementally, each time finalization advances to a new
+    /// message: it simply recomputes the set of ancestors to drop from `finalized_msg`'s
+    /// own justification and filters them out of `latest_msgs`, it does not assume
+    /// anything about what was pruned previously.
+    pub fn prune(&mut self, finalized_msg: &M, mode: LegacyPruningMode) {
+        let prunable = Self::finalized_ancestors(finalized_msg, mode);
+        if prunable.is_empty() {
+            return;
+        }
+        let retained: HashMap<M::Sender, HashSet<M>> = self
+            .latest_msgs
+            .iter()
+            .filter_map(|(sender, msgs)| {
+                let kept: HashSet<M> = msgs.iter().filter(|m| !prunable.contains(m)).cloned().collect();
+                if kept.is_empty() {
+                    None
+                } else {
+                    Some((sender.clone(), kept))
+                }
+            })
+            .collect();
+        self.latest_msgs = LegacyLatestMsgs(retained);
+
+        // a pruned message still directly justifying a retained one keeps a reference
+        // and stays fully retrievable; everything else is released, which only
+        // downgrades it to a summary once nothing retained references it anymore
+        let still_referenced: HashSet<M> = self
+            .latest_msgs
+            .iter()
+            .flat_map(|(_, msgs)| msgs.iter().flat_map(|m| m.justification().iter().cloned()))
+            .collect();
+        for ancestor in &prunable {
+            if still_referenced.contains(ancestor) {
+                self.gc.retain(ancestor);
+            } else {
+                self.gc.release(ancestor);
+            }
+        }
+    }
+
+    /// The `(sender, estimate)` summary a pruned ancestor was downgraded to, if `prune`
+    /// has collected it. See [`LegacyGarbageCollector`].
+    pub fn pruned_summary(&self, msg: &M) -> Option<(&M::Sender, &M::Estimate)> {
+        self.gc.summary_of(msg)
+    }
+
+    /// Walks `finalized_msg`'s transitive justification closure and returns the set of
+    /// ancestor messages that `mode` says are safe to drop.
+    fn finalized_ancestors(finalized_msg: &M, mode: LegacyPruningMode) -> HashSet<M> {
+        let keep_after_depth = match mode {
+            LegacyPruningMode::Archive => return HashSet::new(),
+            LegacyPruningMode::PruneBelowFinalized => 0,
+            LegacyPruningMode::KeepLastRounds(depth) => depth,
+        };
+
+        let mut prunable = HashSet::new();
+        let mut frontier: Vec<M> = finalized_msg.justification().iter().cloned().collect();
+        let mut seen: HashSet<M> = frontier.iter().cloned().collect();
+        let mut depth = 0;
+        while !frontier.is_empty() {
+            if depth >= keep_after_depth {
+                prunable.extend(frontier.iter().cloned());
+            }
+            frontier = frontier
+                .iter()
+                .flat_map(|m| m.justification().iter().cloned())
+                .filter(|m| seen.insert(m.clone()))
+                .collect();
+            depth += 1;
+        }
+        prunable
+    }
+
+    /// Accumulated weight this `LegacySenderState`'s cache attributes to `msg`, without
+    /// re-walking the justification DAG. See [`LegacyWeightCache`].
+    pub fn cached_weight_of(&self, msg: &M) -> WeightUnit {
+        self.weight_cache.weight_of(msg)
+    }
+
+    /// Derives an estimate from this `LegacySenderState`'s own, already-incrementally
+    /// maintained `latest_msgs` instead of `LegacyJustification::mk_estimate`'s
+    /// `LegacyLatestMsgs::from(&LegacyJustification)`, which BFS-walks the entire justification DAG
+    /// on every call. Must always agree with `LegacyJustification::mk_estimate` run against the
+    /// same set of messages; the two are cross-checked in tests.
+    pub fn cached_estimate(
+        &self,
+        senders_weights: &SendersWeight<M::Sender>,
+    ) -> Result<M::Estimate, &'static str> {
+        let latest_msgs_honest = LegacyLatestMsgsHonest::from_latest_msgs(&self.latest_msgs, &self.equivocators);
+        latest_msgs_honest.mk_estimate(senders_weights)
+    }
+
     /// get msgs and fault weight overhead and equivocators overhead sorted
     /// by fault weight overhead against the current sender_state
     pub fn sort_by_faultweight<'z>(&self, msgs: HashSet<&'z M>) -> Vec<&'z M> {
@@ -438,3 +788,323 @@ impl<M: message::Trait> SenderState<M> {
             .collect()
     }
 }
+
+// --- New-world justification types -----------------------------------------------------
+//
+// `LatestMessages`/`LatestMessagesHonest`/`Justification` below are what `validator::State`,
+// `message::Message`, and `codec`/`vote_collector`/`pow`/`threshold_signature` actually use.
+// They are written generically over a `JustifiedMessage` rather than directly over
+// `crate::message::Message<E>` so the per-sender bookkeeping (`update`, `detect_equivocators`,
+// the BFS in `From<&Justification<E>>`) is expressed once; the only type ever instantiating
+// them in this crate is `Message<E>`, via the blanket impl just below.
+
+/// Bridges `LatestMessages`/`LatestMessagesHonest` to whatever message type tracks a
+/// sender's votes. The crate only ever instantiates these with
+/// `crate::message::Message<E>`, whose `sender`/`depends`/`equivocates` are inherent
+/// methods rather than trait methods -- this trait (and the blanket impl below) is what
+/// lets the bookkeeping in this module be written once, generically, instead of against
+/// `Message<E>` directly.
+pub trait JustifiedMessage: Eq + Clone + std::hash::Hash {
+    type Sender: crate::validator::ValidatorName;
+
+    fn sender(&self) -> &Self::Sender;
+    fn depends(&self, other: &Self) -> bool;
+    fn equivocates(&self, other: &Self) -> bool;
+}
+
+impl<E: crate::estimator::Estimator> JustifiedMessage for crate::message::Message<E> {
+    type Sender = E::ValidatorName;
+
+    fn sender(&self) -> &Self::Sender {
+        crate::message::Message::sender(self)
+    }
+
+    fn depends(&self, other: &Self) -> bool {
+        crate::message::Message::depends(self, other)
+    }
+
+    fn equivocates(&self, other: &Self) -> bool {
+        crate::message::Message::equivocates(self, other)
+    }
+}
+
+/// Mapping between senders and their latest messages: every message from a sender that is
+/// not in the dependency of another of their own messages. A sender with more than one
+/// entry here is equivocating -- see `detect_equivocators`.
+#[derive(Clone, Debug)]
+pub struct LatestMessages<M: JustifiedMessage>(HashMap<M::Sender, HashSet<M>>);
+
+impl<M: JustifiedMessage> Default for LatestMessages<M> {
+    fn default() -> Self {
+        LatestMessages(HashMap::new())
+    }
+}
+
+impl<M: JustifiedMessage> LatestMessages<M> {
+    /// Create an empty map.
+    pub fn empty() -> Self {
+        LatestMessages(HashMap::new())
+    }
+
+    /// Insert a new set of messages for a sender.
+    pub fn insert(&mut self, k: M::Sender, v: HashSet<M>) -> Option<HashSet<M>> {
+        self.0.insert(k, v)
+    }
+
+    pub fn contains_key(&self, k: &M::Sender) -> bool {
+        self.0.contains_key(k)
+    }
+
+    pub fn get(&self, k: &M::Sender) -> Option<&HashSet<M>> {
+        self.0.get(k)
+    }
+
+    pub fn get_mut(&mut self, k: &M::Sender) -> Option<&mut HashSet<M>> {
+        self.0.get_mut(k)
+    }
+
+    pub fn iter(&self) -> std::collections::hash_map::Iter<M::Sender, HashSet<M>> {
+        self.0.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn keys(&self) -> std::collections::hash_map::Keys<M::Sender, HashSet<M>> {
+        self.0.keys()
+    }
+
+    pub fn values(&self) -> std::collections::hash_map::Values<'_, M::Sender, HashSet<M>> {
+        self.0.values()
+    }
+
+    /// Merges `new_msg` into this map, returning whether it is itself a latest message
+    /// (the first message seen from its sender, or independent from/newer than every
+    /// message already on file from that sender).
+    pub fn update(&mut self, new_msg: &M) -> bool {
+        let sender = new_msg.sender();
+        if let Some(latest_msgs_from_sender) = self.get(sender).cloned() {
+            latest_msgs_from_sender
+                .iter()
+                .filter(|&old_msg| new_msg != old_msg)
+                .fold(false, |acc, old_msg| {
+                    let new_independent_from_old = !new_msg.depends(old_msg);
+                    // equivocation, old and new do not depend on each other
+                    if new_independent_from_old && !old_msg.depends(new_msg) {
+                        self.get_mut(sender)
+                            .map(|msgs| msgs.insert(new_msg.clone()))
+                            .unwrap_or(false)
+                            || acc
+                    }
+                    // new actually older than old
+                    else if new_independent_from_old {
+                        false || acc
+                    }
+                    // new newer than old
+                    else {
+                        self.get_mut(sender)
+                            .map(|msgs| msgs.remove(old_msg) && msgs.insert(new_msg.clone()))
+                            .unwrap_or(false)
+                            || acc
+                    }
+                })
+        } else {
+            // no message found for this sender, so new_msg is the latest
+            self.insert(sender.clone(), [new_msg.clone()].iter().cloned().collect());
+            true
+        }
+    }
+
+    /// The existing latest message from `msg_new`'s sender that `msg_new` equivocates
+    /// with, if any.
+    fn find_conflict(&self, msg_new: &M) -> Option<M> {
+        self.get(msg_new.sender())
+            .and_then(|latest_msgs| latest_msgs.iter().find(|m| m.equivocates(msg_new)).cloned())
+    }
+
+    /// Detects senders that are currently equivocating, and their summed fault weight as
+    /// tracked by `weights`. A sender holding more than one latest message is already
+    /// equivocating (`update` only ever keeps pairwise causally-incomparable tips for a
+    /// given sender); this re-confirms that invariant with `depends` rather than trusting
+    /// set size alone.
+    pub fn detect_equivocators<U>(
+        &self,
+        weights: &crate::validator::Weights<M::Sender, U>,
+    ) -> (HashSet<M::Sender>, U)
+    where
+        U: crate::util::weight::WeightUnit + Copy + std::ops::Add<Output = U>,
+    {
+        self.0.iter().fold(
+            (HashSet::new(), U::ZERO),
+            |(mut equivocators, mut fault_weight), (sender, msgs)| {
+                let is_equivocating = msgs.len() > 1
+                    && msgs.iter().all(|m| {
+                        msgs.iter()
+                            .filter(|&n| n != m)
+                            .all(|n| !m.depends(n) && !n.depends(m))
+                    });
+                if is_equivocating && equivocators.insert(sender.clone()) {
+                    fault_weight = fault_weight + weights.weight(sender).unwrap_or(U::ZERO);
+                }
+                (equivocators, fault_weight)
+            },
+        )
+    }
+}
+
+/// The subset of `LatestMessages` that is uncontested: every sender's sole latest
+/// message, once senders known to be equivocating (who by definition hold more than one)
+/// have been filtered out.
+#[derive(Clone, Debug)]
+pub struct LatestMessagesHonest<M: JustifiedMessage>(HashSet<M>);
+
+impl<M: JustifiedMessage> LatestMessagesHonest<M> {
+    fn new() -> Self {
+        LatestMessagesHonest(HashSet::new())
+    }
+
+    fn insert(&mut self, msg: M) -> bool {
+        self.0.insert(msg)
+    }
+
+    /// Keeps only the senders in `latest_messages` who hold exactly one latest message
+    /// and are not in `equivocators`.
+    pub fn from_latest_messages(
+        latest_messages: &LatestMessages<M>,
+        equivocators: &HashSet<M::Sender>,
+    ) -> Self {
+        latest_messages
+            .iter()
+            .filter_map(|(sender, msgs)| {
+                if equivocators.contains(sender) || msgs.len() != 1 {
+                    None
+                } else {
+                    msgs.iter().next()
+                }
+            })
+            .fold(LatestMessagesHonest::new(), |mut acc, msg| {
+                acc.insert(msg.clone());
+                acc
+            })
+    }
+
+    pub fn iter(&self) -> std::collections::hash_set::Iter<M> {
+        self.0.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl<E: crate::estimator::Estimator> LatestMessagesHonest<crate::message::Message<E>> {
+    /// Folds this honest frontier into a new estimate via `E::estimate`.
+    pub fn make_estimate<U: crate::util::weight::WeightUnit>(
+        &self,
+        weights: &crate::validator::Weights<E::ValidatorName, U>,
+    ) -> Result<E, E::Error> {
+        E::estimate(self, weights)
+    }
+}
+
+/// The set of messages a message cites as having already been seen before it was built --
+/// the crate's actual justification DAG, as opposed to `LegacyJustification` above. Holds
+/// `Message<E>`s directly (a `HashSet`, since `Message<E>`'s own equality and hash are
+/// defined by content id) rather than being generic over `JustifiedMessage`, since unlike
+/// `LatestMessages`/`LatestMessagesHonest` it is never used with any other message type.
+#[derive(Clone, Debug)]
+pub struct Justification<E: crate::estimator::Estimator>(HashSet<crate::message::Message<E>>);
+
+impl<E: crate::estimator::Estimator> PartialEq for Justification<E> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<E: crate::estimator::Estimator> Eq for Justification<E> {}
+
+impl<E: crate::estimator::Estimator> Justification<E> {
+    /// Create an empty justification, e.g. for a genesis message with no ancestors.
+    pub fn empty() -> Self {
+        Justification(HashSet::new())
+    }
+
+    pub fn iter(&self) -> std::collections::hash_set::Iter<crate::message::Message<E>> {
+        self.0.iter()
+    }
+
+    pub fn par_iter(&self) -> rayon::collections::hash_set::Iter<crate::message::Message<E>> {
+        self.0.par_iter()
+    }
+
+    pub fn contains(&self, msg: &crate::message::Message<E>) -> bool {
+        self.0.contains(msg)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Adds `msg`, returning whether it was not already present.
+    pub fn insert(&mut self, msg: crate::message::Message<E>) -> bool {
+        self.0.insert(msg)
+    }
+}
+
+/// A message's claimed id does not match the id recomputed from its own content. This
+/// should only happen if the message was corrupted in storage, or a peer is lying about
+/// an id to slip a forged or tampered message past a `Justification`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IntegrityError;
+
+impl<E: crate::estimator::Estimator> Justification<E> {
+    /// Like `insert`, but first checks `msg`'s `crate::util::id::Id::getid()` against its
+    /// `id()` and rejects the message on a mismatch rather than trusting it and letting a
+    /// corrupted or forged message poison estimates downstream. Mirrors the
+    /// checksum-on-ingest pattern of content-addressed storage: the digest is recomputed,
+    /// not merely re-read -- for `Message<E>`, `getid` rehashes the inner `ProtoMessage`
+    /// while `id` returns the value cached at construction (see `Message`'s own `Id` impl).
+    pub fn insert_verified(&mut self, msg: crate::message::Message<E>) -> Result<bool, IntegrityError> {
+        use crate::util::id::Id;
+        if msg.getid() != msg.id() {
+            return Err(IntegrityError);
+        }
+        Ok(self.insert(msg))
+    }
+}
+
+impl<E: crate::estimator::Estimator> From<&Justification<E>> for LatestMessages<crate::message::Message<E>> {
+    /// Extracts the latest messages from a justification by walking back from its direct
+    /// members through their own justifications, same BFS `LegacyLatestMsgs::from` used.
+    fn from(justification: &Justification<E>) -> Self {
+        let mut latest_messages = LatestMessages::empty();
+        let mut queue: VecDeque<crate::message::Message<E>> = justification.iter().cloned().collect();
+        while let Some(msg) = queue.pop_front() {
+            if latest_messages.update(&msg) {
+                msg.justification()
+                    .iter()
+                    .for_each(|m| queue.push_back(m.clone()));
+            }
+        }
+        latest_messages
+    }
+}
+
+impl<E: crate::estimator::Estimator> From<LatestMessagesHonest<crate::message::Message<E>>> for Justification<E> {
+    fn from(honest: LatestMessagesHonest<crate::message::Message<E>>) -> Self {
+        Justification(honest.0)
+    }
+}
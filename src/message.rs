@@ -26,7 +26,7 @@ use rayon::prelude::*;
 use serde::Serialize;
 
 use crate::estimator::Estimator;
-use crate::justification::{Justification, LatestMessagesHonest};
+use crate::justification::{Justification, LatestMessages, LatestMessagesHonest};
 use crate::util::hash::Hash;
 use crate::util::id::Id;
 use crate::util::weight::WeightUnit;
@@ -36,6 +36,19 @@ use crate::validator;
 pub enum Error<E: std::error::Error> {
     Estimator(E),
     NoNewMessage,
+    /// The estimate recomputed from the justification does not match the one the message
+    /// actually carries.
+    InvalidEstimate,
+    /// A message appears in the transitive closure of its own justification.
+    CyclicJustification,
+    /// The justification accrues more equivocation weight than the caller's fault
+    /// tolerance allows.
+    FaultThresholdExceeded,
+    /// A `SignedMessage`'s signature does not verify against the claimed public key.
+    InvalidSignature,
+    /// A `PowMessage`'s nonce does not meet the difficulty its size and declared TTL
+    /// require.
+    InsufficientProofOfWork,
 }
 
 impl<E: std::error::Error> std::fmt::Display for Error<E> {
@@ -43,18 +56,65 @@ impl<E: std::error::Error> std::fmt::Display for Error<E> {
         match self {
             Error::Estimator(err) => std::fmt::Display::fmt(&err, f),
             Error::NoNewMessage => writeln!(f, "No message could be added to the state"),
+            Error::InvalidEstimate => {
+                writeln!(f, "Recomputed estimate does not match the message's estimate")
+            }
+            Error::CyclicJustification => {
+                writeln!(f, "Message depends on itself through its own justification")
+            }
+            Error::FaultThresholdExceeded => writeln!(
+                f,
+                "Justification's accrued equivocation weight exceeds the fault threshold"
+            ),
+            Error::InvalidSignature => {
+                writeln!(f, "Signature does not verify against the claimed public key")
+            }
+            Error::InsufficientProofOfWork => writeln!(
+                f,
+                "Nonce does not meet the difficulty required for this message's size and TTL"
+            ),
         }
     }
 }
 
 impl<E: std::error::Error> std::error::Error for Error<E> {}
 
+/// An [`Estimator`] whose values can be folded together and unpacked back apart, so that
+/// several pending votes built from the same justification can collapse into a single
+/// broadcast [`Message`] instead of one message apiece (see [`Message::coalesce`]).
+/// Message count is this crate's cost metric, so this is opt-in per estimator rather than
+/// a bound on `Estimator` itself -- an estimator like a blockchain fork-choice, where two
+/// distinct votes can't generally be merged into one, is free to not implement it.
+///
+/// [`Estimator`]: ../estimator/trait.Estimator.html
+/// [`Message`]: struct.Message.html
+/// [`Message::coalesce`]: struct.Message.html#method.coalesce
+pub trait Coalescible: Sized {
+    /// Combines `estimates` into one, or `None` if any two are irreconcilable (e.g. votes
+    /// for mutually exclusive outcomes).
+    fn coalesce(estimates: &[Self]) -> Option<Self>;
+
+    /// The inverse of [`coalesce`]: the component estimates this value was built from. A
+    /// value that was never coalesced splits into just itself.
+    ///
+    /// [`coalesce`]: #tymethod.coalesce
+    fn split(&self) -> Vec<Self>;
+}
+
 // Mathematical definition of a casper message with (value, validator, justification).
-#[derive(Clone, Eq, PartialEq)]
+#[derive(Clone, Eq, PartialEq, Debug)]
 struct ProtoMessage<E: Estimator> {
     estimate: E,
     sender: E::ValidatorName,
     justification: Justification<E>,
+    /// Identifies which [`validator::Genesis`] epoch this message was produced under.
+    /// [`validator::State::update`] refuses to admit a message stamped with any fork
+    /// index other than its own, so a hard fork's justifications cannot be mixed with
+    /// the epoch that preceded it.
+    ///
+    /// [`validator::Genesis`]: ../validator/struct.Genesis.html
+    /// [`validator::State::update`]: ../validator/struct.State.html#method.update
+    fork: u32,
 }
 
 impl<E: Estimator> Id for ProtoMessage<E> {
@@ -65,11 +125,13 @@ impl<E: Estimator> Serialize for ProtoMessage<E> {
     fn serialize<T: serde::Serializer>(&self, serializer: T) -> Result<T::Ok, T::Error> {
         use serde::ser::SerializeStruct;
 
-        let mut message = serializer.serialize_struct("Message", 3)?;
-        let justification: Vec<_> = self.justification.iter().map(Message::id).collect();
+        let mut message = serializer.serialize_struct("Message", 4)?;
+        let mut justification: Vec<_> = self.justification.iter().map(Message::id).collect();
+        justification.sort_unstable();
         message.serialize_field("sender", &self.sender)?;
         message.serialize_field("estimate", &self.estimate)?;
         message.serialize_field("justification", &justification)?;
+        message.serialize_field("fork", &self.fork)?;
         message.end()
     }
 }
@@ -116,11 +178,34 @@ impl<E: Estimator> Message<E> {
         &self.0.justification
     }
 
+    /// Which [`validator::Genesis`] epoch this message was stamped with at creation.
+    ///
+    /// [`validator::Genesis`]: ../validator/struct.Genesis.html
+    pub fn fork(&self) -> u32 {
+        self.0.fork
+    }
+
     pub fn new(sender: E::ValidatorName, justification: Justification<E>, estimate: E) -> Self {
+        Self::new_with_fork(sender, justification, estimate, 0)
+    }
+
+    /// Like [`new`], but stamps the message with `fork` rather than assuming the
+    /// initial epoch. [`from_validator_state`] is the usual way to reach this: it stamps
+    /// with the producing `validator::State`'s own current fork index.
+    ///
+    /// [`new`]: #method.new
+    /// [`from_validator_state`]: #method.from_validator_state
+    pub fn new_with_fork(
+        sender: E::ValidatorName,
+        justification: Justification<E>,
+        estimate: E,
+        fork: u32,
+    ) -> Self {
         let proto = ProtoMessage {
             sender,
             justification,
             estimate,
+            fork,
         };
         // Message is not mutable, id is computed only once at creation
         let id = proto.id();
@@ -129,13 +214,23 @@ impl<E: Estimator> Message<E> {
 
     /// Creates a message from newly received messages contained in
     /// [`validator_state`], which is used to compute the [`latest honest messages`].
+    /// Stamped with `validator_state`'s current [`fork index`], so a later `State::update`
+    /// elsewhere can tell which epoch produced it.
     ///
     /// [`validator_state`]: ../validator/struct.State.html
     /// [`latest honest messages`]: ../justification/struct.LatestMessagesHonest.html
+    /// Fails with `Error::FaultThresholdExceeded` when the state's accrued fault weight is
+    /// above its fault threshold, rather than committing to an estimate.
+    ///
+    /// [`fork index`]: ../validator/struct.Genesis.html#method.fork_index
     pub fn from_validator_state<U: WeightUnit>(
         sender: E::ValidatorName,
         validator_state: &validator::State<E, U>,
     ) -> Result<Self, Error<E::Error>> {
+        if validator_state.fault_weight() > validator_state.fault_threshold() {
+            return Err(Error::FaultThresholdExceeded);
+        }
+
         let latest_messages_honest = LatestMessagesHonest::from_latest_messages(
             validator_state.latests_messages(),
             validator_state.equivocators(),
@@ -149,44 +244,149 @@ impl<E: Estimator> Message<E> {
             let estimate =
                 latest_messages_honest.make_estimate(&validator_state.validators_weights());
             estimate
-                .map(|estimate| Self::new(sender, justification, estimate))
+                .map(|estimate| {
+                    Self::new_with_fork(
+                        sender,
+                        justification,
+                        estimate,
+                        validator_state.genesis().fork_index(),
+                    )
+                })
                 .map_err(Error::Estimator)
         }
     }
 
-    /// Parses every messages accessible from `self` and `other` by iterating over messages'
-    /// [`justifications`] and returns true if any of those messages is an equivocation with
-    /// another one. This method can only be used to know that a random validator is
-    /// equivocating but not which one.
+    /// Folds `messages` into a single message carrying their combined estimate, or
+    /// `None` if they don't share a sender, fork and justification, or their estimates
+    /// can't be merged (see [`Coalescible::coalesce`]). The reciprocal of [`split`].
     ///
-    /// This method is currently broken as it does not always find equivocations that should be
-    /// accessible from the given messages. It is not commutative. It compares messages with
-    /// themselves.
+    /// [`Coalescible::coalesce`]: trait.Coalescible.html#tymethod.coalesce
+    /// [`split`]: #method.split
+    pub fn coalesce(messages: &[Self]) -> Option<Self>
+    where
+        E: Coalescible,
+    {
+        let first = messages.first()?;
+        let sender = first.sender();
+        let justification = first.justification();
+        let fork = first.fork();
+
+        if !messages
+            .iter()
+            .all(|m| m.sender() == sender && m.justification() == justification && m.fork() == fork)
+        {
+            return None;
+        }
+
+        let estimates: Vec<E> = messages.iter().map(|m| m.estimate().clone()).collect();
+        let estimate = E::coalesce(&estimates)?;
+
+        Some(Self::new_with_fork(
+            sender.clone(),
+            justification.clone(),
+            estimate,
+            fork,
+        ))
+    }
+
+    /// Recomputes `self`'s estimate from its own justification and checks it matches the
+    /// estimate the message actually carries — the check a node runs, GRANDPA-style,
+    /// before trusting a message received from an untrusted peer, since a received or
+    /// deserialized message can carry an arbitrary `estimate` regardless of what its
+    /// `justification` actually supports.
     ///
-    /// [`justifications`]: ../justification/struct.Justification.html
-    pub fn equivocates_indirect(
+    /// Also rejects a justification that depends on `self` (a cycle, which would make
+    /// "recompute the estimate" ill-defined) and one whose accrued equivocation weight
+    /// exceeds `fault_threshold`, since an over-faulty justification cannot be trusted to
+    /// produce a meaningful estimate at all.
+    pub fn validate<U: WeightUnit>(
         &self,
-        other: &Self,
-        mut equivocators: HashSet<E::ValidatorName>,
-    ) -> (bool, HashSet<E::ValidatorName>) {
-        let is_equivocation = self.equivocates(other);
-        let init = if is_equivocation {
-            equivocators.insert(self.sender().clone());
-            (true, equivocators)
+        weights: &validator::Weights<E::ValidatorName, U>,
+        equivocators: &HashSet<E::ValidatorName>,
+        fault_threshold: U,
+    ) -> Result<(), Error<E::Error>> {
+        if self
+            .justification()
+            .iter()
+            .any(|ancestor| ancestor.depends(self))
+        {
+            return Err(Error::CyclicJustification);
+        }
+
+        let fault_weight = equivocators
+            .iter()
+            .filter_map(|sender| weights.weight(sender).ok())
+            .fold(U::ZERO, |acc, w| acc + w);
+        if fault_weight > fault_threshold {
+            return Err(Error::FaultThresholdExceeded);
+        }
+
+        let latest_messages_honest = LatestMessagesHonest::from_latest_messages(
+            &LatestMessages::from(self.justification()),
+            equivocators,
+        );
+
+        let recomputed_estimate = latest_messages_honest
+            .make_estimate(weights)
+            .map_err(Error::Estimator)?;
+
+        if &recomputed_estimate == self.estimate() {
+            Ok(())
         } else {
-            (false, equivocators)
-        };
-        self.justification().iter().fold(
-            init,
-            |(acc_has_equivocations, acc_equivocators), self_prime| {
-                // Note the rotation between other and self, done because descending only on self,
-                // thus other has to become self on the recursion to get its justification visited.
-                let (has_equivocation, equivocators) =
-                    other.equivocates_indirect(self_prime, acc_equivocators.clone());
-                let acc_equivocators = acc_equivocators.union(&equivocators).cloned().collect();
-                (acc_has_equivocations || has_equivocation, acc_equivocators)
-            },
-        )
+            Err(Error::InvalidEstimate)
+        }
+    }
+
+    /// Returns every validator equivocating somewhere within the combined justification
+    /// closure of `self` and `other`.
+    ///
+    /// Replaces the old `equivocates_indirect`, which was not commutative, compared
+    /// messages with themselves, and missed equivocations only reachable at depth. This
+    /// instead computes the full transitive closure of both messages' justifications
+    /// (including the two roots), buckets the result by [`sender`], and for every sender
+    /// holding two or more distinct messages tests each unordered pair for mutual
+    /// independence (neither [`depends`] on the other). Any such pair marks that sender
+    /// as an equivocator. Because it operates on the combined closure rather than
+    /// descending `self` and `other` in lockstep, the result does not depend on
+    /// argument order.
+    ///
+    /// [`sender`]: #method.sender
+    /// [`depends`]: #method.depends
+    pub fn detect_equivocators(&self, other: &Self) -> HashSet<E::ValidatorName> {
+        fn closure<E: Estimator>(msg: &Message<E>, seen: &mut HashSet<Message<E>>) {
+            if seen.insert(msg.clone()) {
+                for parent in msg.justification().iter() {
+                    closure(parent, seen);
+                }
+            }
+        }
+
+        let mut seen = HashSet::new();
+        closure(self, &mut seen);
+        closure(other, &mut seen);
+
+        let mut by_sender: std::collections::HashMap<E::ValidatorName, Vec<&Message<E>>> =
+            std::collections::HashMap::new();
+        for msg in &seen {
+            by_sender.entry(msg.sender().clone()).or_default().push(msg);
+        }
+
+        by_sender
+            .into_iter()
+            .filter_map(|(sender, msgs)| {
+                for i in 0..msgs.len() {
+                    for n in &msgs[i + 1..] {
+                        let m = msgs[i];
+                        // Reuse `depends`'s parallel short-circuit rather than writing a
+                        // second traversal just for this check.
+                        if m.id() != n.id() && !m.depends(n) && !n.depends(m) {
+                            return Some(sender);
+                        }
+                    }
+                }
+                None
+            })
+            .collect()
     }
 
     /// Math definition of the equivocation.
@@ -197,6 +397,24 @@ impl<E: Estimator> Message<E> {
             && !self.depends(other)
     }
 
+    /// When `self` and `other` share a sender and neither depends on the other, returns
+    /// the minimal proof of that equivocation: the conflicting pair plus the sender. A
+    /// third party can hand this to [`MisbehaviorProof::verify`] to independently confirm
+    /// the fault without needing the full justification DAG it was extracted from.
+    ///
+    /// [`MisbehaviorProof::verify`]: struct.MisbehaviorProof.html#method.verify
+    pub fn extract_equivocation_proof(&self, other: &Self) -> Option<MisbehaviorProof<E>> {
+        if self.equivocates(other) {
+            Some(MisbehaviorProof {
+                sender: self.sender().clone(),
+                first: self.clone(),
+                second: other.clone(),
+            })
+        } else {
+            None
+        }
+    }
+
     /// Checks whether self depends on other or not. Returns true if other is somewhere in the
     /// [`justification`] of self. Then recursively checks the justifications of the messages in the
     /// [`justification`] of self.  This check is heavy and works well only with messages where the
@@ -244,6 +462,31 @@ impl<E: Estimator> Message<E> {
         let visited = Arc::new(RwLock::new(HashSet::new()));
         recurse(self, other, visited)
     }
+
+    /// Unpacks a message back into one message per component estimate it was built from
+    /// (see [`Coalescible::split`]), each sharing `self`'s sender, justification and
+    /// fork. The reciprocal of [`coalesce`]; a message that was never coalesced splits
+    /// into a single-element vec equal to itself.
+    ///
+    /// [`Coalescible::split`]: trait.Coalescible.html#tymethod.split
+    /// [`coalesce`]: #method.coalesce
+    pub fn split(&self) -> Vec<Self>
+    where
+        E: Coalescible,
+    {
+        self.estimate()
+            .split()
+            .into_iter()
+            .map(|estimate| {
+                Self::new_with_fork(
+                    self.sender().clone(),
+                    self.justification().clone(),
+                    estimate,
+                    self.fork(),
+                )
+            })
+            .collect()
+    }
 }
 
 impl<E: Estimator> Id for Message<E> {
@@ -253,6 +496,18 @@ impl<E: Estimator> Id for Message<E> {
     fn id(&self) -> Self::ID {
         self.1
     }
+
+    // `getid`'s crate-wide default hashes a value's `Debug` output, but `Message`'s own
+    // `Debug` impl is deliberately terse (sender + estimate only, kept readable for
+    // generative-test GIFs) and omits the justification and fork that are actually part
+    // of a message's identity. Left un-overridden, `getid` would hash a different,
+    // incomplete representation than `id` does, so the two would disagree on any message
+    // with a justification or a nonzero fork. Instead `getid` recomputes the hash from the
+    // inner `ProtoMessage` exactly as `Message::new` did, while `id` returns the cached
+    // value -- the pair `EquivocationProof::verify` compares.
+    fn getid(&self) -> Self::ID {
+        self.0.id()
+    }
 }
 
 impl<E: Estimator> Serialize for Message<E> {
@@ -280,6 +535,95 @@ impl<E: Estimator> Debug for Message<E> {
     }
 }
 
+/// A portable, independently-verifiable record of one validator double-voting: the two
+/// conflicting messages plus the sender name, analogous to a statement-table misbehavior
+/// record. Unlike [`Message::detect_equivocators`], which needs the full justification
+/// DAG to find equivocators in the first place, a `MisbehaviorProof` lets a third party
+/// confirm the fault from just the two embedded messages.
+///
+/// [`Message::detect_equivocators`]: struct.Message.html#method.detect_equivocators
+#[derive(Clone, Debug)]
+pub struct MisbehaviorProof<E: Estimator> {
+    sender: E::ValidatorName,
+    first: Message<E>,
+    second: Message<E>,
+}
+
+impl<E: Estimator> MisbehaviorProof<E> {
+    pub fn sender(&self) -> &E::ValidatorName {
+        &self.sender
+    }
+
+    pub fn messages(&self) -> (&Message<E>, &Message<E>) {
+        (&self.first, &self.second)
+    }
+
+    /// Re-checks the `equivocates` predicate on the embedded pair, so a third party can
+    /// independently confirm the fault without the full DAG this proof was extracted
+    /// from.
+    pub fn verify(&self) -> bool {
+        self.first.equivocates(&self.second)
+    }
+}
+
+impl<E: Estimator> Serialize for MisbehaviorProof<E> {
+    fn serialize<T: serde::Serializer>(&self, serializer: T) -> Result<T::Ok, T::Error> {
+        use serde::ser::SerializeStruct;
+
+        // Each conflicting message is serialized the same id-based way `Message` already
+        // is: its own fields, with its justification encoded as a list of message ids.
+        let mut proof = serializer.serialize_struct("MisbehaviorProof", 3)?;
+        proof.serialize_field("sender", &self.sender)?;
+        proof.serialize_field("first", &self.first)?;
+        proof.serialize_field("second", &self.second)?;
+        proof.end()
+    }
+}
+
+/// A [`MisbehaviorProof`] built to outlive the `validator::State` that found it: `verify`
+/// additionally recomputes each embedded message's id from its own content (`getid`) and
+/// compares it with the id the message carries (`id`) before
+/// re-checking the conflict, the same integrity check [`Justification::insert_verified`]
+/// runs on ingest, so a tampered or forged message cannot ride along inside an
+/// otherwise-valid-looking proof. This is what [`validator::State::update`] collects as
+/// it admits messages and [`validator::State::equivocation_proofs`] hands to an external
+/// slashing or reporting layer.
+///
+/// [`Justification::insert_verified`]: ../justification/struct.Justification.html#method.insert_verified
+/// [`validator::State::update`]: ../validator/struct.State.html#method.update
+/// [`validator::State::equivocation_proofs`]: ../validator/struct.State.html#method.equivocation_proofs
+#[derive(Clone, Debug)]
+pub struct EquivocationProof<E: Estimator>(MisbehaviorProof<E>);
+
+impl<E: Estimator> EquivocationProof<E> {
+    pub fn sender(&self) -> &E::ValidatorName {
+        self.0.sender()
+    }
+
+    pub fn messages(&self) -> (&Message<E>, &Message<E>) {
+        self.0.messages()
+    }
+
+    /// Re-checks that `first` and `second` each hash to the id they claim, then re-checks
+    /// the pair still conflicts, independent of the `State` that extracted this proof.
+    pub fn verify(&self) -> bool {
+        let (first, second) = self.0.messages();
+        first.id() == first.getid() && second.id() == second.getid() && self.0.verify()
+    }
+}
+
+impl<E: Estimator> From<MisbehaviorProof<E>> for EquivocationProof<E> {
+    fn from(proof: MisbehaviorProof<E>) -> Self {
+        EquivocationProof(proof)
+    }
+}
+
+impl<E: Estimator> Serialize for EquivocationProof<E> {
+    fn serialize<T: serde::Serializer>(&self, serializer: T) -> Result<T::Ok, T::Error> {
+        self.0.serialize(serializer)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -289,9 +633,56 @@ mod test {
     use std::collections::HashSet;
     use std::iter::FromIterator;
 
-    use crate::justification::LatestMessages;
     use crate::validator;
 
+    /// Sums the yes/no tallies: coalescing several `VoteCount` estimates is exactly
+    /// tallying several ballots together, and splitting a tally back apart recovers one
+    /// single-vote `VoteCount` per yes and per no it accrued.
+    impl Coalescible for VoteCount {
+        fn coalesce(estimates: &[Self]) -> Option<Self> {
+            Some(estimates.iter().fold(
+                VoteCount { yes: 0, no: 0 },
+                |acc, v| VoteCount {
+                    yes: acc.yes + v.yes,
+                    no: acc.no + v.no,
+                },
+            ))
+        }
+
+        fn split(&self) -> Vec<Self> {
+            std::iter::repeat(VoteCount { yes: 1, no: 0 })
+                .take(self.yes as usize)
+                .chain(std::iter::repeat(VoteCount { yes: 0, no: 1 }).take(self.no as usize))
+                .collect()
+        }
+    }
+
+    #[test]
+    fn coalesce_merges_same_justification_votes_and_split_recovers_them() {
+        let v0 = VoteCount::create_vote_message(0, true);
+        let v1 = VoteCount::create_vote_message(0, false);
+
+        let coalesced = Message::coalesce(&[v0.clone(), v1.clone()])
+            .expect("same sender, fork and justification must coalesce");
+        assert_eq!(*coalesced.estimate(), VoteCount { yes: 1, no: 1 });
+        assert_eq!(coalesced.sender(), v0.sender());
+        assert_eq!(coalesced.justification(), v0.justification());
+
+        let mut split = coalesced.split();
+        split.sort_by_key(|m| m.estimate().no);
+        assert_eq!(split.len(), 2);
+        assert_eq!(*split[0].estimate(), VoteCount { yes: 1, no: 0 });
+        assert_eq!(*split[1].estimate(), VoteCount { yes: 0, no: 1 });
+    }
+
+    #[test]
+    fn coalesce_refuses_to_merge_different_senders() {
+        let v0 = VoteCount::create_vote_message(0, true);
+        let v1 = VoteCount::create_vote_message(1, true);
+
+        assert!(Message::coalesce(&[v0, v1]).is_none());
+    }
+
     #[test]
     fn message_equality() {
         let validator_state = validator::State::new(
@@ -401,15 +792,18 @@ mod test {
     }
 
     #[test]
-    fn message_equivocates_indirect_direct_equivocation() {
+    fn message_detect_equivocators_direct_equivocation() {
         let v0 = VoteCount::create_vote_message(0, false);
         let v0_prime = VoteCount::create_vote_message(0, true);
 
-        assert!(v0.equivocates_indirect(&v0_prime, HashSet::new()).0);
+        assert_eq!(
+            v0.detect_equivocators(&v0_prime),
+            HashSet::from_iter(vec![0])
+        );
     }
 
     #[test]
-    fn message_equivocates_indirect_semi_direct() {
+    fn message_detect_equivocators_semi_direct() {
         let mut validator_state = validator::State::new(
             validator::Weights::new(vec![(0, 1.0), (1, 1.0), (2, 1.0)].into_iter().collect()),
             0.0,
@@ -434,15 +828,15 @@ mod test {
         validator_state.update(&[&v1]);
         let m2 = Message::from_validator_state(2, &validator_state).unwrap();
 
-        assert!(m2.equivocates_indirect(&m1, HashSet::new()).0);
+        assert_eq!(m2.detect_equivocators(&m1), HashSet::from_iter(vec![1]));
 
         // Cannot see future messages
-        assert!(!m2.equivocates_indirect(&v0, HashSet::new()).0);
-        assert!(!v0.equivocates_indirect(&v1, HashSet::new()).0);
+        assert!(m2.detect_equivocators(&v0).is_empty());
+        assert!(v0.detect_equivocators(&v1).is_empty());
     }
 
     #[test]
-    fn message_equivocates_indirect_commutativity() {
+    fn message_detect_equivocators_commutativity() {
         let mut validator_state = validator::State::new(
             validator::Weights::new(vec![(0, 1.0), (1, 1.0), (2, 1.0)].into_iter().collect()),
             0.0,
@@ -467,31 +861,19 @@ mod test {
         validator_state.update(&[&v1]);
         let m2 = Message::from_validator_state(2, &validator_state).unwrap();
 
-        // Messages are tried for equivocation in the following order:
-        // 1. for m1.equivocates_indirect(m2):
-        //     1. m1 _|_ m2
-        //     2. m2 _|_ v0
-        //     3. v0 _|_ v0
-        //     4. v0 _|_ v1
-        //
-        // 2. for m2.equivocates_indirect(m1):
-        //     1. m2 _|_ m1
-        //     2. m1 _|_ v0
-        //     3. v0 _|_ v0
-        //     4. m1 _|_ v1
-        //     5. v1 _|_ v0
-        //
-        // We can see that:
-        // 1. The method is not commutative;
-        // 2. It does not try every combinations of messages;
-        // 3. It compares v0 with itself in both instances.
-
-        assert!(!m1.equivocates_indirect(&m2, HashSet::new()).0);
-        assert!(m2.equivocates_indirect(&m1, HashSet::new()).0);
+        // Unlike the old `equivocates_indirect`, both orderings agree: the closure of
+        // `m1` and `m2` is the same set of messages regardless of which side it is
+        // computed from.
+        assert_eq!(
+            m1.detect_equivocators(&m2),
+            m2.detect_equivocators(&m1),
+            "detect_equivocators must be commutative"
+        );
+        assert_eq!(m1.detect_equivocators(&m2), HashSet::from_iter(vec![1]));
     }
 
     #[test]
-    fn message_equivocates_indirect_total_indirection() {
+    fn message_detect_equivocators_total_indirection() {
         let mut validator_state = validator::State::new(
             validator::Weights::new(
                 vec![(0, 1.0), (1, 1.0), (2, 1.0), (3, 1.0)]
@@ -528,7 +910,43 @@ mod test {
         // In this case, only 1 is equivocating. m1 and v1 are independant of each other. Neither
         // m2 or m3 are faulty messages but they are on different protocol branches created by
         // 1's equivocation.
-        assert!(m2.equivocates_indirect(&m3, HashSet::new()).0);
+        assert_eq!(m2.detect_equivocators(&m3), HashSet::from_iter(vec![1]));
+    }
+
+    #[test]
+    fn extract_equivocation_proof() {
+        let v0 = VoteCount::create_vote_message(0, false);
+        let v0_prime = VoteCount::create_vote_message(0, true);
+        let v1 = VoteCount::create_vote_message(1, true);
+
+        let proof = v0.extract_equivocation_proof(&v0_prime).unwrap();
+        assert_eq!(*proof.sender(), 0);
+        assert!(proof.verify());
+
+        assert!(v0.extract_equivocation_proof(&v1).is_none());
+    }
+
+    #[test]
+    fn state_update_collects_equivocation_proofs() {
+        let mut validator_state = validator::State::new(
+            validator::Weights::new(vec![(0, 1.0), (1, 1.0)].into_iter().collect()),
+            0.0,
+            LatestMessages::empty(),
+            4.0,
+            HashSet::new(),
+        );
+
+        let v0 = &VoteCount::create_vote_message(0, false);
+        let v0_prime = &VoteCount::create_vote_message(0, true);
+
+        validator_state.update(&[v0]);
+        assert!(validator_state.equivocation_proofs().is_empty());
+
+        validator_state.update(&[v0_prime]);
+        let proofs = validator_state.equivocation_proofs();
+        assert_eq!(proofs.len(), 1);
+        assert_eq!(*proofs[0].sender(), 0);
+        assert!(proofs[0].verify());
     }
 
     #[test]
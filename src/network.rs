@@ -0,0 +1,186 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::estimator::Estimator;
+use crate::message::{Error, Message};
+use crate::util::hash::Hash;
+use crate::util::id::Id;
+use crate::util::weight::WeightUnit;
+use crate::validator;
+
+/// Starting reputation given to a newly seen peer.
+pub const INITIAL_REPUTATION: i64 = 100;
+/// Reputation penalty applied when a peer sends an equivocating message.
+pub const EQUIVOCATION_PENALTY: i64 = 50;
+/// Reputation penalty applied when a peer sends a message that fails verification.
+pub const UNVERIFIABLE_PENALTY: i64 = 20;
+/// Reputation at or below which a peer is considered abusive and should be throttled or
+/// dropped.
+pub const REPUTATION_FLOOR: i64 = 0;
+
+/// Which message hashes a given peer is already known to have acknowledged, so a node
+/// only ever sends what that peer is actually missing instead of re-broadcasting
+/// everything it has.
+#[derive(Default)]
+pub struct View {
+    acknowledged: HashSet<Hash>,
+}
+
+impl View {
+    pub fn new() -> Self {
+        View::default()
+    }
+
+    pub fn acknowledge(&mut self, id: Hash) {
+        self.acknowledged.insert(id);
+    }
+
+    pub fn has(&self, id: &Hash) -> bool {
+        self.acknowledged.contains(id)
+    }
+
+    /// The subset of `ids` this peer has not yet acknowledged.
+    pub fn missing<'a>(&self, ids: impl IntoIterator<Item = &'a Hash>) -> Vec<Hash> {
+        ids.into_iter()
+            .filter(|id| !self.has(id))
+            .cloned()
+            .collect()
+    }
+}
+
+struct PeerState {
+    view: View,
+    reputation: i64,
+}
+
+impl Default for PeerState {
+    fn default() -> Self {
+        PeerState {
+            view: View::new(),
+            reputation: INITIAL_REPUTATION,
+        }
+    }
+}
+
+/// What happened when a node tried to admit a message received from a peer.
+pub enum ReceiveOutcome<E: Estimator> {
+    /// The message was accepted and `validator_state` updated.
+    Accepted,
+    /// The validator state had nothing new to learn from this message (a stale resend);
+    /// not itself a sign of misbehavior.
+    NoNewMessage,
+    /// The message depends on justification ancestors this node does not have yet.
+    /// These should be requested from the sending peer before retrying.
+    MissingAncestors(Vec<Hash>),
+    /// The message failed verification outright (bad signature, fault threshold
+    /// exceeded, ...); the sending peer's reputation has already been penalized.
+    Unverifiable(Error<E::Error>),
+}
+
+/// Distributes `Message<E>` among validators and tracks, per peer, which messages it has
+/// already acknowledged plus an anti-spam reputation score, turning the otherwise purely
+/// local `validator::State::update` into a working peer-to-peer protocol.
+pub struct Network<P: Eq + std::hash::Hash + Clone> {
+    peers: HashMap<P, PeerState>,
+}
+
+impl<P: Eq + std::hash::Hash + Clone> Network<P> {
+    pub fn new() -> Self {
+        Network {
+            peers: HashMap::new(),
+        }
+    }
+
+    fn peer_mut(&mut self, peer: &P) -> &mut PeerState {
+        self.peers
+            .entry(peer.clone())
+            .or_insert_with(PeerState::default)
+    }
+
+    pub fn reputation(&self, peer: &P) -> i64 {
+        self.peers
+            .get(peer)
+            .map(|p| p.reputation)
+            .unwrap_or(INITIAL_REPUTATION)
+    }
+
+    /// Whether `peer`'s reputation has dropped low enough that it should be throttled or
+    /// dropped rather than served or trusted further.
+    pub fn is_abusive(&self, peer: &P) -> bool {
+        self.reputation(peer) <= REPUTATION_FLOOR
+    }
+
+    /// The subset of `ids` that `peer` is missing, according to its tracked `View`. A
+    /// never-before-seen peer is treated as missing everything.
+    pub fn missing_for(&self, peer: &P, ids: impl IntoIterator<Item = Hash>) -> Vec<Hash> {
+        let ids: Vec<Hash> = ids.into_iter().collect();
+        self.peers
+            .get(peer)
+            .map(|p| p.view.missing(ids.iter()))
+            .unwrap_or(ids)
+    }
+
+    pub fn record_sent(&mut self, peer: &P, id: Hash) {
+        self.peer_mut(peer).view.acknowledge(id);
+    }
+
+    pub fn penalize(&mut self, peer: &P, amount: i64) {
+        self.peer_mut(peer).reputation -= amount;
+    }
+
+    pub fn penalize_equivocation(&mut self, peer: &P) {
+        self.penalize(peer, EQUIVOCATION_PENALTY);
+    }
+
+    /// Attempts to admit `message`, received from `peer` and claiming to be `sender`'s,
+    /// into `validator_state`.
+    ///
+    /// If the message's justification references ancestors `validator_state` has never
+    /// seen, admission is deferred and those ancestor hashes are returned so the caller
+    /// can fetch them from `peer` and retry, rather than silently dropping the message.
+    /// If admission otherwise fails verification, `peer`'s reputation is penalized.
+    pub fn receive<E: Estimator, U: WeightUnit>(
+        &mut self,
+        peer: &P,
+        sender: E::ValidatorName,
+        message: Message<E>,
+        validator_state: &mut validator::State<E, U>,
+    ) -> ReceiveOutcome<E> {
+        // everything reachable from the latest messages, not just the latest messages
+        let mut known: HashSet<Hash> = HashSet::new();
+        let mut stack: Vec<&Message<E>> =
+            validator_state.latests_messages().values().flatten().collect();
+        while let Some(msg) = stack.pop() {
+            if known.insert(msg.id()) {
+                stack.extend(msg.justification().iter());
+            }
+        }
+        let missing_ancestors: Vec<Hash> = message
+            .justification()
+            .iter()
+            .map(Message::id)
+            .filter(|id| !known.contains(id))
+            .collect();
+        if !missing_ancestors.is_empty() {
+            return ReceiveOutcome::MissingAncestors(missing_ancestors);
+        }
+
+        validator_state.update(&[&message]);
+        match Message::from_validator_state(sender, validator_state) {
+            Ok(_) => {
+                self.record_sent(peer, message.id());
+                ReceiveOutcome::Accepted
+            }
+            Err(Error::NoNewMessage) => ReceiveOutcome::NoNewMessage,
+            Err(err) => {
+                self.penalize(peer, UNVERIFIABLE_PENALTY);
+                ReceiveOutcome::Unverifiable(err)
+            }
+        }
+    }
+}
+
+impl<P: Eq + std::hash::Hash + Clone> Default for Network<P> {
+    fn default() -> Self {
+        Network::new()
+    }
+}
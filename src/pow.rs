@@ -0,0 +1,239 @@
+use std::collections::HashSet;
+use std::ops::Add;
+
+use crate::codec::WireCodec;
+use crate::estimator::Estimator;
+use crate::message::{Error, Message};
+use crate::util::id::blake2b_hash;
+use crate::util::weight::{WeightUnit, U256};
+use crate::validator::{State, ValidatorName};
+
+/// How steeply proof-of-work difficulty scales with a message's serialized size and
+/// declared time-to-live, the dials a deployment tunes to set its own spam resistance
+/// without touching estimator logic. Difficulty grows by one required leading-zero bit
+/// per `bytes_per_extra_bit` bytes and one more per `seconds_per_extra_bit` seconds of
+/// TTL, on top of a flat `base_difficulty` every message pays regardless of size.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PowParams {
+    pub base_difficulty: u32,
+    pub bytes_per_extra_bit: u64,
+    pub seconds_per_extra_bit: u64,
+}
+
+impl PowParams {
+    /// The number of leading zero bits a message of `size_bytes` declaring `ttl_seconds`
+    /// must grind into its proof-of-work hash to be admitted.
+    pub fn required_difficulty(&self, size_bytes: u64, ttl_seconds: u64) -> u32 {
+        self.base_difficulty
+            + (size_bytes / self.bytes_per_extra_bit.max(1)) as u32
+            + (ttl_seconds / self.seconds_per_extra_bit.max(1)) as u32
+    }
+}
+
+/// Number of leading zero bits in `bytes`, read most-significant byte first -- the
+/// Whisper/Hashcash difficulty measure this module grinds for.
+fn leading_zero_bits(bytes: &[u8]) -> u32 {
+    let mut zero_bits = 0;
+    for byte in bytes {
+        if *byte == 0 {
+            zero_bits += 8;
+        } else {
+            zero_bits += byte.leading_zeros();
+            break;
+        }
+    }
+    zero_bits
+}
+
+/// Widens a `u128` into a `U256`, since `uint::construct_uint!` only gives us `From<u64>`
+/// to build on.
+fn u256_from_u128(value: u128) -> U256 {
+    U256::from(value as u64) | (U256::from((value >> 64) as u64) << 64)
+}
+
+fn pow_hash(payload: &[u8], nonce: u64) -> crate::util::hash::Hash {
+    let mut preimage = payload.to_vec();
+    preimage.extend_from_slice(&nonce.to_be_bytes());
+    blake2b_hash(&preimage)
+}
+
+/// A [`Message`] paired with a ground proof-of-work nonce and the time-to-live it was
+/// ground for, borrowing Whisper's admission-control scheme: the sender spends CPU work
+/// proportional to how much space and how long their message asks the network to carry
+/// it, so flooding the store costs real work rather than being free.
+#[derive(Clone)]
+pub struct PowMessage<E: Estimator> {
+    message: Message<E>,
+    ttl_seconds: u64,
+    nonce: u64,
+}
+
+impl<E> PowMessage<E>
+where
+    E: Estimator + WireCodec,
+    E::ValidatorName: WireCodec,
+{
+    /// Grinds nonces starting from zero until `message`'s proof-of-work hash meets the
+    /// difficulty `params` requires for its encoded size and `ttl_seconds`, and returns
+    /// the result. Blocking and unbounded: the caller is the one spending the work this
+    /// scheme exists to require.
+    pub fn mine(message: Message<E>, ttl_seconds: u64, params: &PowParams) -> Self {
+        let payload = message.encode();
+        let difficulty = params.required_difficulty(payload.len() as u64, ttl_seconds);
+
+        let mut nonce = 0u64;
+        loop {
+            if leading_zero_bits(pow_hash(&payload, nonce).as_bytes()) >= difficulty {
+                return PowMessage {
+                    message,
+                    ttl_seconds,
+                    nonce,
+                };
+            }
+            nonce += 1;
+        }
+    }
+
+    pub fn message(&self) -> &Message<E> {
+        &self.message
+    }
+
+    pub fn ttl_seconds(&self) -> u64 {
+        self.ttl_seconds
+    }
+
+    /// Re-derives the difficulty this message's size and TTL require under `params` and
+    /// checks its nonce actually meets it.
+    pub fn verify(&self, params: &PowParams) -> Result<(), Error<E::Error>> {
+        if self.leading_zero_bits() >= self.required_difficulty(params) {
+            Ok(())
+        } else {
+            Err(Error::InsufficientProofOfWork)
+        }
+    }
+
+    fn required_difficulty(&self, params: &PowParams) -> u32 {
+        params.required_difficulty(self.message.encode().len() as u64, self.ttl_seconds)
+    }
+
+    fn leading_zero_bits(&self) -> u32 {
+        leading_zero_bits(pow_hash(&self.message.encode(), self.nonce).as_bytes())
+    }
+
+    /// This message's ground work, and the size/TTL cost it was ground against, kept as
+    /// an exact `(work, cost)` pair rather than a single float ratio -- comparing two
+    /// pairs by cross-multiplication, the same trick [`crate::util::weight::Rational256`]
+    /// uses, gives every store the same eviction order regardless of platform.
+    fn work_per_byte_per_ttl(&self) -> (u128, u128) {
+        let work = 1u128 << self.leading_zero_bits().min(127);
+        let cost = (self.message.encode().len() as u128).max(1) * (self.ttl_seconds as u128).max(1);
+        (work, cost)
+    }
+}
+
+/// A size-bounded collection of [`PowMessage`]s: once their combined encoded size
+/// exceeds `byte_budget`, the lowest work-per-byte-per-TTL entries are evicted first,
+/// so an attacker can't force out well-proven messages just by flooding the store with
+/// many cheaply-proven ones.
+pub struct PowMessageStore<E: Estimator> {
+    messages: Vec<PowMessage<E>>,
+    byte_budget: u64,
+}
+
+impl<E> PowMessageStore<E>
+where
+    E: Estimator + WireCodec,
+    E::ValidatorName: WireCodec,
+{
+    pub fn new(byte_budget: u64) -> Self {
+        PowMessageStore {
+            messages: Vec::new(),
+            byte_budget,
+        }
+    }
+
+    pub fn messages(&self) -> &[PowMessage<E>] {
+        &self.messages
+    }
+
+    fn total_bytes(&self) -> u64 {
+        self.messages
+            .iter()
+            .map(|m| m.message.encode().len() as u64)
+            .sum()
+    }
+
+    /// Admits `message` unconditionally, then evicts the weakest entries (lowest
+    /// work-per-byte-per-TTL first) until the store is back within `byte_budget` -- so a
+    /// message that no longer meets the bar can be pruned even after admission, rather
+    /// than only ever being rejected up front.
+    pub fn insert(&mut self, message: PowMessage<E>) {
+        self.messages.push(message);
+        self.prune();
+    }
+
+    fn prune(&mut self) {
+        while self.total_bytes() > self.byte_budget && !self.messages.is_empty() {
+            let weakest = self
+                .messages
+                .iter()
+                .enumerate()
+                .min_by(|(_, a), (_, b)| {
+                    let (a_work, a_cost) = a.work_per_byte_per_ttl();
+                    let (b_work, b_cost) = b.work_per_byte_per_ttl();
+                    // `a_work`/`b_work` run up to `1 << 127` and `a_cost`/`b_cost` fold in
+                    // an attacker-controlled `ttl_seconds`, so the cross-multiplication a
+                    // plain `u128 * u128` would need to compare the two ratios can overflow
+                    // for realistic inputs. Widen to `U256` (the same type
+                    // `Rational256` already cross-multiplies in) so the eviction order
+                    // stays correct instead of wrapping.
+                    let lhs = u256_from_u128(a_work)
+                        .checked_mul(u256_from_u128(b_cost))
+                        .expect("pow work/cost cross-multiplication overflowed U256");
+                    let rhs = u256_from_u128(b_work)
+                        .checked_mul(u256_from_u128(a_cost))
+                        .expect("pow work/cost cross-multiplication overflowed U256");
+                    lhs.cmp(&rhs)
+                })
+                .map(|(index, _)| index);
+
+            match weakest {
+                Some(index) => {
+                    self.messages.remove(index);
+                }
+                None => break,
+            }
+        }
+    }
+}
+
+impl<E, U> State<E, U>
+where
+    E: Estimator + WireCodec,
+    E::ValidatorName: ValidatorName + WireCodec,
+    U: WeightUnit + Copy,
+{
+    /// Like [`State::update`], but only admits [`PowMessage`]s whose ground nonce meets
+    /// this state's [`PowParams`] (see [`State::enable_pow`]); if proof-of-work has not
+    /// been enabled, every message is admitted unconditionally, matching `update`'s own
+    /// behavior.
+    ///
+    /// [`State::update`]: #method.update
+    /// [`State::enable_pow`]: #method.enable_pow
+    pub fn update_proven(&mut self, messages: &[&PowMessage<E>]) -> HashSet<E::ValidatorName>
+    where
+        U: Add<Output = U> + PartialOrd,
+    {
+        let params = self.pow_params().copied();
+        let verified: Vec<&Message<E>> = messages
+            .iter()
+            .filter(|proven| match &params {
+                Some(params) => proven.verify(params).is_ok(),
+                None => true,
+            })
+            .map(|proven| proven.message())
+            .collect();
+
+        self.update(&verified)
+    }
+}
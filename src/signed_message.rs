@@ -0,0 +1,120 @@
+use std::collections::{HashMap, HashSet};
+use std::ops::Add;
+
+use ed25519_dalek::{Keypair, PublicKey, Signature, Signer, Verifier};
+
+use crate::estimator::Estimator;
+use crate::message::{Error, Message};
+use crate::util::id::Id;
+use crate::util::weight::WeightUnit;
+use crate::validator::{State, ValidatorName};
+
+/// A [`Message`] paired with a signature over `(sender, estimate, justification hash)`,
+/// so a receiver can verify authorship cryptographically instead of trusting the bare
+/// [`sender`] field.
+///
+/// A message's [`id`] is already a hash over that content (the `Debug` rendering of its
+/// `ProtoMessage`: sender, estimate, justification and fork), so it is reused here as the
+/// signing payload rather than re-deriving it.
+///
+/// Two conflicting `SignedMessage`s from the same sender are a non-repudiable,
+/// forwardable equivocation proof: unlike plain [`Message::equivocates`], which only
+/// proves a fault to a node that already holds both messages, the embedded signatures
+/// let any third party verify the fault on its own.
+///
+/// [`Message`]: ../message/struct.Message.html
+/// [`sender`]: ../message/struct.Message.html#method.sender
+/// [`id`]: ../message/struct.Message.html
+/// [`Message::equivocates`]: ../message/struct.Message.html#method.equivocates
+#[derive(Clone)]
+pub struct SignedMessage<E: Estimator> {
+    message: Message<E>,
+    public_key: PublicKey,
+    signature: Signature,
+}
+
+impl<E: Estimator> SignedMessage<E> {
+    /// Signs `message` with `keypair`, over its `(sender, estimate, justification hash)`
+    /// id.
+    pub fn sign(message: Message<E>, keypair: &Keypair) -> Self {
+        let signature = keypair.sign(message.id().as_bytes());
+        SignedMessage {
+            message,
+            public_key: keypair.public,
+            signature,
+        }
+    }
+
+    pub fn message(&self) -> &Message<E> {
+        &self.message
+    }
+
+    pub fn public_key(&self) -> &PublicKey {
+        &self.public_key
+    }
+
+    /// Verifies the embedded signature was produced by `pubkey` over this message's id.
+    pub fn verify(&self, pubkey: &PublicKey) -> Result<(), Error<E::Error>> {
+        pubkey
+            .verify(self.message.id().as_bytes(), &self.signature)
+            .map_err(|_| Error::InvalidSignature)
+    }
+
+    /// Verifies the signature and, only if it checks out, hands back the inner
+    /// `Message`. Untrusted input (received over the network, deserialized, ...) should
+    /// be routed through this before `LatestMessages::update` or
+    /// `Message::from_validator_state` see it, so a forged signature can never be
+    /// attributed to an honest sender and used to frame them as an equivocator.
+    pub fn into_verified(self, pubkey: &PublicKey) -> Result<Message<E>, Error<E::Error>> {
+        self.verify(pubkey)?;
+        Ok(self.message)
+    }
+
+    /// If the two signed messages' inner messages equivocate, returns both as a
+    /// non-repudiable fault proof: anyone holding this pair can verify both signatures
+    /// and the equivocation independently, without re-deriving it from the full
+    /// justification DAG.
+    pub fn equivocation_proof(&self, other: &Self) -> Option<(Self, Self)> {
+        if self.message.equivocates(&other.message) {
+            Some((self.clone(), other.clone()))
+        } else {
+            None
+        }
+    }
+}
+
+impl<E, U> State<E, U>
+where
+    E: Estimator,
+    E::ValidatorName: ValidatorName,
+    U: WeightUnit + Copy,
+{
+    /// Like [`State::update`], but only admits messages whose embedded signature
+    /// verifies against `known_keys`' entry for their claimed sender -- a forgery, or a
+    /// genuine signature under a key `known_keys` doesn't attribute to that sender, is
+    /// dropped rather than handed to the equivocation/fault-weight machinery, so it can
+    /// never be used to frame an honest validator.
+    ///
+    /// [`State::update`]: #method.update
+    pub fn update_signed(
+        &mut self,
+        messages: &[&SignedMessage<E>],
+        known_keys: &HashMap<E::ValidatorName, PublicKey>,
+    ) -> HashSet<E::ValidatorName>
+    where
+        U: Add<Output = U> + PartialOrd,
+    {
+        let verified: Vec<&Message<E>> = messages
+            .iter()
+            .filter(|signed| {
+                known_keys
+                    .get(signed.message().sender())
+                    .map(|pubkey| pubkey == signed.public_key() && signed.verify(pubkey).is_ok())
+                    .unwrap_or(false)
+            })
+            .map(|signed| signed.message())
+            .collect();
+
+        self.update(&verified)
+    }
+}
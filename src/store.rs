@@ -0,0 +1,83 @@
+use std::collections::HashMap;
+
+use crate::estimator::Estimator;
+use crate::message::Message;
+use crate::util::hash::Hash;
+use crate::util::id::Id;
+
+/// Persists the message set backing `LatestMessages`/`LatestMessagesHonest`, so a node
+/// can recover its full view — including which senders are equivocators — after a
+/// restart instead of rebuilding it from scratch.
+///
+/// Messages reference their justification ancestors by value; a `Store` keys everything
+/// by content-addressed id instead, so the transitive justification DAG gets a single
+/// deduplicated home regardless of how many latest messages reference the same ancestor.
+///
+/// `validator::State` is not generic over a `Store` and `Message::from_validator_state`
+/// does not read from one: both still hold justification ancestors by value.
+pub trait Store<E: Estimator> {
+    /// Inserts `message`, keyed by its content-addressed id. Returns `false` if a
+    /// message with that id was already present — since messages are immutable and
+    /// content-addressed, that is always the same message, never a conflict.
+    fn insert(&mut self, message: Message<E>) -> bool;
+
+    /// The most recently inserted message from `sender`, if the store has seen one.
+    /// Note this is a single pointer, not the full honest/equivocating latest-message
+    /// set `LatestMessages` tracks; callers reconstructing that still need to insert
+    /// every message from `sender`, not just call this once.
+    fn get_latest(&self, sender: &E::ValidatorName) -> Option<&Message<E>>;
+
+    /// Whether a message with this id is present in the store.
+    fn contains(&self, id: &Hash) -> bool;
+
+    /// Looks a message up by id, e.g. to resolve a justification ancestor without
+    /// cloning it out of another message.
+    fn get(&self, id: &Hash) -> Option<&Message<E>>;
+}
+
+/// The default, non-persistent `Store`: everything lives in memory and is lost on
+/// restart. A disk-backed `Store` (sled, LMDB, ...) implements the same trait and can be
+/// swapped in without touching the code that consumes it.
+pub struct MemoryStore<E: Estimator> {
+    by_id: HashMap<Hash, Message<E>>,
+    latest_by_sender: HashMap<E::ValidatorName, Hash>,
+}
+
+impl<E: Estimator> MemoryStore<E> {
+    pub fn new() -> Self {
+        MemoryStore {
+            by_id: HashMap::new(),
+            latest_by_sender: HashMap::new(),
+        }
+    }
+}
+
+impl<E: Estimator> Default for MemoryStore<E> {
+    fn default() -> Self {
+        MemoryStore::new()
+    }
+}
+
+impl<E: Estimator> Store<E> for MemoryStore<E> {
+    fn insert(&mut self, message: Message<E>) -> bool {
+        let id = message.id();
+        let sender = message.sender().clone();
+        let inserted = self.by_id.insert(id, message).is_none();
+        self.latest_by_sender.insert(sender, id);
+        inserted
+    }
+
+    fn get_latest(&self, sender: &E::ValidatorName) -> Option<&Message<E>> {
+        self.latest_by_sender
+            .get(sender)
+            .and_then(|id| self.by_id.get(id))
+    }
+
+    fn contains(&self, id: &Hash) -> bool {
+        self.by_id.contains_key(id)
+    }
+
+    fn get(&self, id: &Hash) -> Option<&Message<E>> {
+        self.by_id.get(id)
+    }
+}
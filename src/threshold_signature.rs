@@ -0,0 +1,226 @@
+use std::collections::{HashMap, HashSet};
+use std::ops::Add;
+
+use curve25519_dalek::constants::ED25519_BASEPOINT_TABLE;
+use curve25519_dalek::edwards::EdwardsPoint;
+use curve25519_dalek::scalar::Scalar;
+use rand::rngs::OsRng;
+use sha2::Sha512;
+
+use crate::estimator::Estimator;
+use crate::message::{Error, Message};
+use crate::util::id::Id;
+use crate::util::weight::WeightUnit;
+use crate::validator::{State, ValidatorName};
+
+/// A validator's position in the signing group, also its Shamir share's x-coordinate.
+pub type ParticipantId = u16;
+
+/// One participant's Shamir share of the group's private signing key, plus the group's
+/// fixed public key every partial and aggregate signature verifies against -- the FROST
+/// "key generation" output. A quorum of `threshold` shares can jointly sign without ever
+/// reconstructing the group secret itself.
+#[derive(Clone)]
+pub struct KeyShare {
+    pub id: ParticipantId,
+    secret_share: Scalar,
+    pub group_public_key: EdwardsPoint,
+}
+
+/// Splits a freshly generated group secret key into `total` Shamir shares of a degree
+/// `threshold - 1` polynomial, any `threshold` of which can jointly produce a signature
+/// verifying against the single returned `group_public_key`. A trusted dealer's
+/// convenience: real deployments would run this as a distributed key generation so no
+/// single party ever holds the group secret, which this function necessarily does for
+/// the moment it takes to split it.
+pub fn generate_shares(threshold: u16, total: u16) -> Vec<KeyShare> {
+    assert!(
+        threshold >= 1 && threshold <= total,
+        "threshold must be between 1 and the total number of participants"
+    );
+
+    let mut rng = OsRng;
+    let coefficients: Vec<Scalar> = (0..threshold).map(|_| Scalar::random(&mut rng)).collect();
+    let group_public_key = &coefficients[0] * &ED25519_BASEPOINT_TABLE;
+
+    (1..=total)
+        .map(|id| {
+            let x = Scalar::from(id as u64);
+            let secret_share = coefficients
+                .iter()
+                .rev()
+                .fold(Scalar::zero(), |acc, coefficient| acc * x + coefficient);
+            KeyShare {
+                id,
+                secret_share,
+                group_public_key,
+            }
+        })
+        .collect()
+}
+
+/// This participant's per-message nonce commitment: the private `Scalar` it must keep
+/// secret until [`partial_sign`], and the public `EdwardsPoint` it publishes to the
+/// coordinator so the other signers' [`partial_sign`] calls can fold it into the
+/// aggregate commitment.
+pub fn commit() -> (Scalar, EdwardsPoint) {
+    let mut rng = OsRng;
+    let nonce = Scalar::random(&mut rng);
+    (nonce, &nonce * &ED25519_BASEPOINT_TABLE)
+}
+
+/// One signer's contribution to a threshold signature over `message`.
+#[derive(Clone)]
+pub struct PartialSignature {
+    id: ParticipantId,
+    commitment: EdwardsPoint,
+    z: Scalar,
+}
+
+/// The Lagrange coefficient for `id` when interpolating the constant term of the
+/// polynomial at the signers in `signer_ids`, the weight FROST gives each partial
+/// signature so the sum of the (weighted) shares reconstructs the group secret's
+/// contribution without ever reconstructing the secret itself.
+fn lagrange_coefficient(id: ParticipantId, signer_ids: &[ParticipantId]) -> Scalar {
+    let x_i = Scalar::from(id as u64);
+    signer_ids
+        .iter()
+        .filter(|&&other| other != id)
+        .fold(Scalar::one(), |acc, &other| {
+            let x_j = Scalar::from(other as u64);
+            acc * x_j * (x_j - x_i).invert()
+        })
+}
+
+/// Every signing participant's published commitment, in the one order every participant
+/// in the session will agree on -- `HashMap`'s own iteration order isn't stable across
+/// processes, and [`binding_factor`] must hash the exact same preimage for everyone.
+fn sorted_commitments(commitments: &HashMap<ParticipantId, EdwardsPoint>) -> Vec<(ParticipantId, EdwardsPoint)> {
+    let mut sorted: Vec<(ParticipantId, EdwardsPoint)> = commitments.iter().map(|(&id, &c)| (id, c)).collect();
+    sorted.sort_unstable_by_key(|&(id, _)| id);
+    sorted
+}
+
+/// `rho_i`, the per-participant binding factor FROST derives from the signer's own id,
+/// the message, and every commitment in the session, before folding commitments into the
+/// aggregate. Without it, this scheme degenerates to the naive flat-summed construction
+/// FROST's binding factor exists to fix: a validator with overlapping signing sessions
+/// (routine for a consensus participant signing concurrent rounds) gives an adversary
+/// enough freedom across the unbound sums to force a forged aggregate signature
+/// (Drijvers et al.'s rogue-nonce/ROS attack). Binding each commitment to this exact
+/// session removes that freedom.
+fn binding_factor(id: ParticipantId, message_id: &[u8], sorted_commitments: &[(ParticipantId, EdwardsPoint)]) -> Scalar {
+    let mut preimage = id.to_le_bytes().to_vec();
+    preimage.extend_from_slice(message_id);
+    for (other_id, commitment) in sorted_commitments {
+        preimage.extend_from_slice(&other_id.to_le_bytes());
+        preimage.extend_from_slice(commitment.compress().as_bytes());
+    }
+    Scalar::hash_from_bytes::<Sha512>(&preimage)
+}
+
+/// The Fiat-Shamir challenge binding a (aggregate) commitment, the group public key, and
+/// the message together, exactly as a single-signer Schnorr scheme would, so the
+/// aggregate signature this module produces verifies with the same equation a
+/// single-signer one does.
+fn challenge(commitment: EdwardsPoint, group_public_key: EdwardsPoint, message_id: &[u8]) -> Scalar {
+    Scalar::hash_from_bytes::<Sha512>(
+        &[
+            commitment.compress().as_bytes().as_slice(),
+            group_public_key.compress().as_bytes().as_slice(),
+            message_id,
+        ]
+        .concat(),
+    )
+}
+
+/// This signer's partial signature over `message`, given its own `nonce` from
+/// [`commit`] and every signing participant's published commitment (including its own).
+pub fn partial_sign<E: Estimator>(
+    share: &KeyShare,
+    nonce: Scalar,
+    commitments: &HashMap<ParticipantId, EdwardsPoint>,
+    message: &Message<E>,
+) -> PartialSignature {
+    let message_id = message.id();
+    let sorted = sorted_commitments(commitments);
+    let rho = |id: ParticipantId| binding_factor(id, message_id.as_bytes(), &sorted);
+
+    let aggregate_commitment: EdwardsPoint = sorted.iter().map(|&(id, c)| rho(id) * c).sum();
+    let signer_ids: Vec<ParticipantId> = commitments.keys().copied().collect();
+    let e = challenge(aggregate_commitment, share.group_public_key, message_id.as_bytes());
+    let lambda = lagrange_coefficient(share.id, &signer_ids);
+    let rho_i = rho(share.id);
+
+    PartialSignature {
+        id: share.id,
+        commitment: rho_i * commitments[&share.id],
+        z: rho_i * nonce + e * lambda * share.secret_share,
+    }
+}
+
+/// A completed FROST signature: the aggregate nonce commitment and the summed response,
+/// verifying against the fixed group public key exactly like a plain Schnorr signature.
+#[derive(Clone, Copy)]
+pub struct ThresholdSignature {
+    commitment: EdwardsPoint,
+    z: Scalar,
+}
+
+/// Sums at least `threshold` partial signatures over the same message and commitment set
+/// into one group signature. The coordinator's role in FROST's final round; it learns
+/// nothing the partials didn't already reveal.
+pub fn aggregate(partials: &[PartialSignature]) -> ThresholdSignature {
+    ThresholdSignature {
+        commitment: partials.iter().map(|partial| partial.commitment).sum(),
+        z: partials.iter().map(|partial| partial.z).sum(),
+    }
+}
+
+/// Verifies a [`ThresholdSignature`] against the group's fixed public key, exactly the
+/// same equation a single-signer Schnorr verification would use: `z * G == R + e * Y`.
+pub fn verify<E: Estimator>(
+    signature: &ThresholdSignature,
+    group_public_key: &EdwardsPoint,
+    message: &Message<E>,
+) -> Result<(), Error<E::Error>> {
+    let e = challenge(signature.commitment, *group_public_key, message.id().as_bytes());
+    let expected = signature.commitment + e * group_public_key;
+    let actual = &signature.z * &ED25519_BASEPOINT_TABLE;
+
+    if actual == expected {
+        Ok(())
+    } else {
+        Err(Error::InvalidSignature)
+    }
+}
+
+impl<E, U> State<E, U>
+where
+    E: Estimator,
+    E::ValidatorName: ValidatorName,
+    U: WeightUnit + Copy,
+{
+    /// Like [`State::update`], but admits a message jointly authored by a quorum of
+    /// validators only once its [`ThresholdSignature`] verifies against the fixed
+    /// `group_public_key` -- so a single finalized message can be attributed to the
+    /// signing quorum as a whole, rather than to any one validator's own key.
+    ///
+    /// [`State::update`]: #method.update
+    pub fn update_threshold_signed(
+        &mut self,
+        signed_messages: &[(Message<E>, ThresholdSignature)],
+        group_public_key: &EdwardsPoint,
+    ) -> HashSet<E::ValidatorName>
+    where
+        U: Add<Output = U> + PartialOrd,
+    {
+        let verified: Vec<&Message<E>> = signed_messages
+            .iter()
+            .filter(|(message, signature)| verify(signature, group_public_key, message).is_ok())
+            .map(|(message, _)| message)
+            .collect();
+
+        self.update(&verified)
+    }
+}
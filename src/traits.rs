@@ -17,7 +17,12 @@ pub trait Estimate: Hash + Clone + Ord + Send + Sync + Debug + Data {
 
 pub trait Data {
     type Data;
-    fn is_valid(&Self::Data) -> bool;
+    /// Context needed to decide whether `data` is acceptable on its own, independent of
+    /// any particular estimate, e.g. the set of transactions already committed along the
+    /// causal history leading up to it, so double-spends and already-included data can
+    /// be rejected.
+    type Context;
+    fn is_valid(data: &Self::Data, context: &Self::Context) -> bool;
 }
 
 pub trait Sender: Hash + Clone + Ord + Eq + Send + Sync + Debug {}
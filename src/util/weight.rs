@@ -0,0 +1,164 @@
+use std::cmp::Ordering;
+use std::ops::{Add, Sub};
+
+use crate::traits::Zero;
+
+uint::construct_uint! {
+    /// A 256-bit unsigned integer, used as the numerator/denominator of [`Rational256`].
+    pub struct U256(4);
+}
+
+/// A validator's stake, foldable across a set of latest messages and totally ordered so
+/// two estimators can agree on which side of a tie-break a message falls on. The folds in
+/// this crate substitute `ZERO`, not `NAN`, for a validator missing from the weight map
+/// entirely; an implementor that can't represent "not a number" (e.g. [`Rational256`]) is
+/// free to make `NAN` an ordinary value, as long as doing so doesn't itself introduce
+/// nondeterminism.
+///
+/// `PartialOrd` rather than `Ord` -- `f64` has no total order of its own (`NAN` compares
+/// false against everything, including itself), so an estimator folding `f64` weights
+/// still has to treat an incomparable tie-break as a real possibility. [`Rational256`]
+/// has no such gap: its `Ord` impl makes every comparison well-defined. `Add` is a
+/// supertrait rather than a bound callers have to repeat at every fold site, since every
+/// `WeightUnit` this crate folds (an `Estimator::estimate` tally, `Weights::sum_all_weights`,
+/// a `Message::validate` fault-weight sum, ...) needs to add two weights together to be
+/// usable at all.
+pub trait WeightUnit:
+    Zero<Self> + Copy + PartialEq + PartialOrd + Add<Output = Self> + Sized
+{
+    const NAN: Self;
+}
+
+impl Zero<f64> for f64 {
+    const ZERO: f64 = 0.0;
+}
+
+impl WeightUnit for f64 {
+    const NAN: f64 = f64::NAN;
+}
+
+/// An exact rational weight, `numerator / denominator` over 256-bit unsigned integers,
+/// so folding validator weights into an estimate is reproducible bit-for-bit regardless
+/// of iteration order or platform -- unlike `f64`, whose addition isn't associative and
+/// whose `NAN` makes `>=` comparisons against a missing weight ill-defined.
+///
+/// Comparisons and addition cross-multiply by the other operand's denominator rather than
+/// reducing to a common float, so there is exactly one correct answer for any pair of
+/// values; overflow of the 256-bit numerator or denominator panics rather than wrapping,
+/// since a silently wrapped weight would reintroduce the nondeterminism this type exists
+/// to remove.
+#[derive(Clone, Copy, Debug)]
+pub struct Rational256 {
+    numerator: U256,
+    denominator: U256,
+}
+
+// Equality goes through `cmp` too, so `1/2 == 2/4` just as `Ord` says they are equal.
+impl PartialEq for Rational256 {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Rational256 {}
+
+impl Rational256 {
+    pub fn new(numerator: U256, denominator: U256) -> Self {
+        assert!(
+            !denominator.is_zero(),
+            "Rational256 denominator must be non-zero"
+        );
+        Rational256 {
+            numerator,
+            denominator,
+        }
+    }
+
+    pub fn numerator(&self) -> U256 {
+        self.numerator
+    }
+
+    pub fn denominator(&self) -> U256 {
+        self.denominator
+    }
+
+    /// `self` and `other` cross-multiplied onto the same (possibly non-reduced) common
+    /// denominator, the shared step `Ord` and `Add` both need to compare or combine two
+    /// rationals without losing precision to a float division.
+    fn cross_multiply(self, other: Self) -> (U256, U256) {
+        let lhs = self
+            .numerator
+            .checked_mul(other.denominator)
+            .expect("Rational256 comparison/addition overflowed the numerator");
+        let rhs = other
+            .numerator
+            .checked_mul(self.denominator)
+            .expect("Rational256 comparison/addition overflowed the numerator");
+        (lhs, rhs)
+    }
+}
+
+impl Zero<Rational256> for Rational256 {
+    const ZERO: Rational256 = Rational256 {
+        numerator: U256([0, 0, 0, 0]),
+        denominator: U256([1, 0, 0, 0]),
+    };
+}
+
+impl WeightUnit for Rational256 {
+    // There is no "not a number" rational; a missing validator weight contributes no
+    // weight to a fold, which is also the safer reading of a missing entry than the
+    // `f64::NAN` this replaces ever was.
+    const NAN: Rational256 = <Rational256 as Zero<Rational256>>::ZERO;
+}
+
+impl Add for Rational256 {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        let denominator = self
+            .denominator
+            .checked_mul(other.denominator)
+            .expect("Rational256 addition overflowed the denominator");
+        let (lhs, rhs) = self.cross_multiply(other);
+        let numerator = lhs
+            .checked_add(rhs)
+            .expect("Rational256 addition overflowed the numerator");
+        Rational256 {
+            numerator,
+            denominator,
+        }
+    }
+}
+
+impl Sub for Rational256 {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        let denominator = self
+            .denominator
+            .checked_mul(other.denominator)
+            .expect("Rational256 subtraction overflowed the denominator");
+        let (lhs, rhs) = self.cross_multiply(other);
+        let numerator = lhs
+            .checked_sub(rhs)
+            .expect("Rational256 subtraction underflowed -- this weight type has no negative representation");
+        Rational256 {
+            numerator,
+            denominator,
+        }
+    }
+}
+
+impl PartialOrd for Rational256 {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Rational256 {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let (lhs, rhs) = self.cross_multiply(*other);
+        lhs.cmp(&rhs)
+    }
+}
@@ -0,0 +1,512 @@
+// Core CBC Casper
+// Copyright (C) 2018 - 2020  Coordination Technology Ltd.
+// Authors: pZ4 <pz4@protonmail.ch>,
+//          Lederstrumpf,
+//          h4sh3d <h4sh3d@truelevel.io>
+//          roflolilolmao <q@truelevel.ch>
+//
+// This file is part of Core CBC Casper.
+//
+// Core CBC Casper is free software: you can redistribute it and/or modify it under the terms
+// of the GNU Affero General Public License as published by the Free Software Foundation, either
+// version 3 of the License, or (at your option) any later version.
+//
+// Core CBC Casper is distributed in the hope that it will be useful, but WITHOUT ANY
+// WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License along with the Core CBC
+// Rust Library. If not, see <https://www.gnu.org/licenses/>.
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::ops::Add;
+
+use crate::estimator::Estimator;
+use crate::justification::LatestMessages;
+use crate::message::{EquivocationProof, Message};
+use crate::util::hash::Hash;
+use crate::util::id::Id;
+use crate::util::weight::WeightUnit;
+use crate::vote_collector::VoteCollector;
+
+/// Anything that can name a validator: equality and hashing so it can key a map, `Clone`
+/// because every latest-message/weight lookup hands back an owned copy, `Debug` since a
+/// validator id ends up in error messages and `Message`'s own `Debug` impl, and `Ord` so
+/// cliques of validators (see [`crate::blockchain::Block::safety_oracles`]) can be
+/// collected into a canonical `BTreeSet`. Blanket-implemented for any type meeting the
+/// bound, so a plain `u32` validator id already qualifies.
+pub trait ValidatorName: Eq + Ord + Clone + std::hash::Hash + std::fmt::Debug {}
+
+impl<T: Eq + Ord + Clone + std::hash::Hash + std::fmt::Debug> ValidatorName for T {}
+
+/// Static map from validator to its consensus weight, shared (via `Clone`) between a
+/// `State` and whatever estimator it is driving.
+#[derive(Clone, Debug, Default)]
+pub struct Weights<V: ValidatorName, U> {
+    weights: HashMap<V, U>,
+}
+
+impl<V: ValidatorName, U: WeightUnit + Copy> Weights<V, U> {
+    pub fn new(weights: HashMap<V, U>) -> Self {
+        Weights { weights }
+    }
+
+    /// `validator`'s configured weight, or an error if it is not part of this set.
+    pub fn weight(&self, validator: &V) -> Result<U, &'static str> {
+        self.weights
+            .get(validator)
+            .copied()
+            .ok_or("validator not found in Weights")
+    }
+
+    pub fn validators(&self) -> impl Iterator<Item = &V> {
+        self.weights.keys()
+    }
+}
+
+impl<V: ValidatorName, U: WeightUnit + Copy + Add<Output = U>> Weights<V, U> {
+    /// Total weight held by every validator in this set, used to derive a fault
+    /// tolerance threshold relative to the active validator set rather than as a
+    /// free-standing constant (see [`State::fork`]).
+    pub fn sum_all_weights(&self) -> U {
+        self.weights.values().fold(U::ZERO, |acc, &w| acc + w)
+    }
+}
+
+/// The root of a validator set's epoch: the weight map in force, a monotonically
+/// increasing `fork_index` identifying which epoch this is, and a `parent_commitment`
+/// binding it to the message frontier the previous epoch ended on (the zero `Hash` for
+/// the very first genesis, which has no parent). `State::fork` is the only way to
+/// produce a non-initial `Genesis`.
+#[derive(Clone, Debug)]
+pub struct Genesis<V: ValidatorName, U> {
+    weights: Weights<V, U>,
+    fork_index: u32,
+    parent_commitment: Hash,
+}
+
+impl<V: ValidatorName, U: WeightUnit + Copy> Genesis<V, U> {
+    /// The very first epoch: fork index `0`, no parent to commit to.
+    fn root(weights: Weights<V, U>) -> Self {
+        Genesis {
+            weights,
+            fork_index: 0,
+            parent_commitment: Hash::default(),
+        }
+    }
+
+    fn rotate(&self, weights: Weights<V, U>, parent_commitment: Hash) -> Self {
+        Genesis {
+            weights,
+            fork_index: self.fork_index + 1,
+            parent_commitment,
+        }
+    }
+
+    pub fn fork_index(&self) -> u32 {
+        self.fork_index
+    }
+
+    pub fn weights(&self) -> &Weights<V, U> {
+        &self.weights
+    }
+
+    /// Commitment to the message frontier of the epoch this one rotated from. `Hash`'s
+    /// all-zero default value for the root genesis, which has no parent epoch.
+    pub fn parent_commitment(&self) -> Hash {
+        self.parent_commitment
+    }
+}
+
+/// Deterministic commitment to a set of latest messages: every message id, sorted so the
+/// result does not depend on iteration order, hashed the same way any other content id
+/// in this crate is (see [`crate::util::id::Id::hash`]).
+fn commit<E: Estimator>(latest_messages: &LatestMessages<Message<E>>) -> Hash {
+    let mut ids: Vec<Hash> = latest_messages
+        .values()
+        .flat_map(|msgs| msgs.iter().map(Message::id))
+        .collect();
+    ids.sort_unstable();
+    let bytes: Vec<u8> = ids
+        .iter()
+        .flat_map(|id| format!("{:?}", id).into_bytes())
+        .collect();
+    <Message<E> as Id>::hash(&bytes)
+}
+
+/// A validator's live view of consensus: which epoch it is following ([`Genesis`]), the
+/// latest message seen from every validator, the validators currently known to be
+/// equivocating and their combined weight, and the fault-tolerance threshold that weight
+/// is checked against. Constructed directly rather than through a builder, following the
+/// same plain-constructor convention as [`Weights::new`] and [`crate::blockchain::Block::new`].
+#[derive(Clone, Debug)]
+pub struct State<E: Estimator, U: WeightUnit>
+where
+    E::ValidatorName: ValidatorName,
+{
+    genesis: Genesis<E::ValidatorName, U>,
+    fault_weight: U,
+    latest_messages: LatestMessages<Message<E>>,
+    thr: U,
+    equivocators: HashSet<E::ValidatorName>,
+    /// Conflicting-message pairs `update` has caught a sender submitting, newest last.
+    /// See [`EquivocationProof`].
+    equivocation_proofs: Vec<EquivocationProof<E>>,
+    /// Round-indexed double-vote detector, absent by default (see
+    /// [`State::enable_vote_collector`]). Once present, `update` feeds it every
+    /// message of the current fork, including one it then declines, instead of falling
+    /// back to the O(n) scan over
+    /// `latest_messages` for that message's sender.
+    vote_collector: Option<VoteCollector<E>>,
+    /// Proof-of-work admission parameters, absent by default (see
+    /// [`State::enable_pow`]). Once present, [`crate::pow::PowMessage`]s presented via
+    /// `crate::pow`'s verifying update entry point must meet the scaled difficulty these
+    /// parameters derive, or be rejected before ever reaching `update`.
+    pow_params: Option<crate::pow::PowParams>,
+}
+
+impl<E, U> State<E, U>
+where
+    E: Estimator,
+    E::ValidatorName: ValidatorName,
+    U: WeightUnit + Copy,
+{
+    pub fn new(
+        weights: Weights<E::ValidatorName, U>,
+        fault_weight: U,
+        latest_messages: LatestMessages<Message<E>>,
+        thr: U,
+        equivocators: HashSet<E::ValidatorName>,
+    ) -> Self {
+        State {
+            genesis: Genesis::root(weights),
+            fault_weight,
+            latest_messages,
+            thr,
+            equivocators,
+            equivocation_proofs: Vec::new(),
+            vote_collector: None,
+            pow_params: None,
+        }
+    }
+
+    /// Opts this state into round-indexed double-vote detection: from this point on,
+    /// `update` checks each sender's message against a [`VoteCollector`] instead of
+    /// scanning every message `latest_messages` already holds from that sender. A no-op
+    /// if already enabled.
+    pub fn enable_vote_collector(&mut self) {
+        self.vote_collector.get_or_insert_with(VoteCollector::new);
+    }
+
+    pub fn vote_collector(&self) -> Option<&VoteCollector<E>> {
+        self.vote_collector.as_ref()
+    }
+
+    /// Opts this state into proof-of-work admission control under `params`: from this
+    /// point on, `crate::pow`'s verifying update entry point requires every message to
+    /// carry a nonce meeting `params`'s scaled difficulty. Overwrites any
+    /// previously-enabled parameters.
+    pub fn enable_pow(&mut self, params: crate::pow::PowParams) {
+        self.pow_params = Some(params);
+    }
+
+    pub fn pow_params(&self) -> Option<&crate::pow::PowParams> {
+        self.pow_params.as_ref()
+    }
+
+    pub fn genesis(&self) -> &Genesis<E::ValidatorName, U> {
+        &self.genesis
+    }
+
+    pub fn validators_weights(&self) -> &Weights<E::ValidatorName, U> {
+        self.genesis.weights()
+    }
+
+    pub fn latests_messages(&self) -> &LatestMessages<Message<E>> {
+        &self.latest_messages
+    }
+
+    pub fn equivocators(&self) -> &HashSet<E::ValidatorName> {
+        &self.equivocators
+    }
+
+    pub fn fault_threshold(&self) -> U {
+        self.thr
+    }
+
+    /// Merges newly-received `messages` into this validator's latest-message view,
+    /// silently dropping any message stamped with a fork identifier other than this
+    /// state's current [`Genesis::fork_index`] -- a justification built under a
+    /// different epoch's validator set cannot be mixed into this one's. Before each
+    /// message is merged, checks it for a conflict with the sender's prior message(s):
+    /// via [`VoteCollector::insert`] if [`Self::enable_vote_collector`] has been called,
+    /// or else the same full per-sender scan as before. A conflict is always recorded as
+    /// an [`EquivocationProof`] (see [`equivocation_proofs`]), but a message from a
+    /// *newly* conflicting sender whose weight would push [`fault_weight`] past `thr` is
+    /// declined rather than merged, so one more equivocation can never silently breach
+    /// this state's own safety margin. Returns the set of senders declined this way; the
+    /// equivocator set and accrued fault weight are recomputed from whatever was
+    /// actually admitted.
+    ///
+    /// [`equivocation_proofs`]: #method.equivocation_proofs
+    /// [`fault_weight`]: #method.fault_weight
+    pub fn update(&mut self, messages: &[&Message<E>]) -> HashSet<E::ValidatorName>
+    where
+        U: Add<Output = U> + PartialOrd,
+    {
+        let mut declined = HashSet::new();
+        // Accrues across this whole batch (starting from the fault weight already on
+        // record) so that two distinct new equivocators admitted in the same call can't
+        // each pass the threshold check against the same stale pre-batch `fault_weight`
+        // and together breach `thr`; `detect_equivocators` below still recomputes the
+        // authoritative `self.fault_weight` from what was actually admitted.
+        let mut pending_fault_weight = self.fault_weight;
+        let mut pending_equivocators: HashSet<E::ValidatorName> = HashSet::new();
+
+        for message in messages {
+            if message.fork() != self.genesis.fork_index() {
+                continue;
+            }
+
+            let proof = match self.vote_collector {
+                Some(ref mut collector) => collector.insert(message),
+                None => self
+                    .latest_messages
+                    .get(message.sender())
+                    .and_then(|latest_from_sender| {
+                        latest_from_sender
+                            .iter()
+                            .find_map(|other| other.extract_equivocation_proof(message))
+                    }),
+            };
+
+            if let Some(proof) = proof {
+                self.equivocation_proofs.push(EquivocationProof::from(proof));
+
+                let sender = message.sender();
+                if !self.equivocators.contains(sender) && !pending_equivocators.contains(sender) {
+                    let sender_weight = self.genesis.weights().weight(sender).unwrap_or(U::ZERO);
+                    if pending_fault_weight + sender_weight > self.thr {
+                        declined.insert(sender.clone());
+                        continue;
+                    }
+                    pending_fault_weight = pending_fault_weight + sender_weight;
+                    pending_equivocators.insert(sender.clone());
+                }
+            }
+
+            self.latest_messages.update(message);
+        }
+
+        let (equivocators, fault_weight) =
+            self.latest_messages.detect_equivocators(&self.genesis.weights);
+        self.equivocators = equivocators;
+        self.fault_weight = fault_weight;
+
+        declined
+    }
+
+    pub fn fault_weight(&self) -> U {
+        self.fault_weight
+    }
+
+    /// How much more equivocating weight this state can admit before its accrued
+    /// [`fault_weight`] breaches `thr` -- an auditable safety margin rather than `thr`
+    /// alone, which says nothing about how close the validator set already is to losing
+    /// its safety guarantee. Once that margin has been exhausted the result is negative
+    /// for `f64`, while `Rational256`'s subtraction panics on the underflow.
+    ///
+    /// [`fault_weight`]: #method.fault_weight
+    pub fn remaining_fault_tolerance(&self) -> U
+    where
+        U: std::ops::Sub<Output = U>,
+    {
+        self.thr - self.fault_weight
+    }
+
+    /// Conflicting-message pairs `update` has caught so far, each independently
+    /// verifiable via [`EquivocationProof::verify`] without needing this `State`'s
+    /// justification DAG.
+    pub fn equivocation_proofs(&self) -> &[EquivocationProof<E>] {
+        &self.equivocation_proofs
+    }
+
+    /// Rotates this state into the next epoch: `new_weights` becomes the active
+    /// validator set, the returned `Genesis` commits to this state's current message
+    /// frontier (see [`commit`]), `latest_messages` and `equivocators` are cleared since
+    /// neither carries over across a hard fork, and `thr` is recomputed as one third of
+    /// `new_weights`'s total weight -- the same byzantine-fault-tolerance fraction this
+    /// crate's own adversarial-weight tests already assume -- rather than being carried
+    /// over as a free-standing number from the old epoch.
+    pub fn fork(&self, new_weights: Weights<E::ValidatorName, U>) -> Self
+    where
+        U: Add<Output = U> + std::ops::Div<Output = U> + From<u8>,
+    {
+        let parent_commitment = commit(&self.latest_messages);
+        let genesis = self.genesis.rotate(new_weights, parent_commitment);
+        let thr = genesis.weights().sum_all_weights() / U::from(3u8);
+
+        State {
+            genesis,
+            fault_weight: U::ZERO,
+            latest_messages: LatestMessages::empty(),
+            thr,
+            equivocators: HashSet::new(),
+            equivocation_proofs: Vec::new(),
+            vote_collector: self.vote_collector.as_ref().map(|_| VoteCollector::new()),
+            pow_params: self.pow_params.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::tests_common::vote_count::VoteCount;
+
+    #[test]
+    fn fork_increments_index_and_clears_state() {
+        let weights = Weights::new(vec![(0, 1.0), (1, 1.0)].into_iter().collect());
+        let mut state: State<VoteCount, f64> =
+            State::new(weights, 0.0, LatestMessages::empty(), 0.0, HashSet::new());
+
+        let v0 = &VoteCount::create_vote_message(0, true);
+        state.update(&[v0]);
+        assert_eq!(state.genesis().fork_index(), 0);
+        assert!(state.latests_messages().len() > 0);
+
+        let new_weights = Weights::new(vec![(0, 2.0), (1, 2.0)].into_iter().collect());
+        let forked = state.fork(new_weights);
+
+        assert_eq!(forked.genesis().fork_index(), 1);
+        assert_eq!(forked.latests_messages().len(), 0);
+        assert!(forked.equivocators().is_empty());
+        assert_eq!(forked.fault_threshold(), 4.0 / 3.0);
+        assert_ne!(
+            forked.genesis().parent_commitment(),
+            Hash::default(),
+            "a fork's parent commitment must reflect the pre-fork frontier",
+        );
+    }
+
+    #[test]
+    fn update_drops_messages_from_a_different_fork() {
+        let weights = Weights::new(vec![(0, 1.0)].into_iter().collect());
+        let state: State<VoteCount, f64> =
+            State::new(weights.clone(), 0.0, LatestMessages::empty(), 0.0, HashSet::new());
+        let mut forked = state.fork(weights);
+
+        let from_old_epoch = &VoteCount::create_vote_message(0, true);
+        forked.update(&[from_old_epoch]);
+
+        assert_eq!(
+            forked.latests_messages().len(),
+            0,
+            "a message stamped with the wrong fork index must not be admitted"
+        );
+    }
+
+    #[test]
+    fn from_validator_state_stamps_current_fork_index() {
+        let weights = Weights::new(vec![(0, 1.0), (1, 1.0)].into_iter().collect());
+        let mut state: State<VoteCount, f64> =
+            State::new(weights.clone(), 0.0, LatestMessages::empty(), 0.0, HashSet::new());
+
+        let v0 = &VoteCount::create_vote_message(0, true);
+        state.update(&[v0]);
+        let message = Message::from_validator_state(1, &state).unwrap();
+
+        assert_eq!(message.fork(), state.genesis().fork_index());
+
+        let forked = state.fork(weights);
+        let message_after_fork = Message::from_validator_state(0, &forked);
+        assert!(
+            message_after_fork.is_err(),
+            "a freshly forked state has no messages yet to build an estimate from"
+        );
+    }
+
+    #[test]
+    fn vote_collector_catches_same_round_double_vote() {
+        let weights = Weights::new(vec![(0, 1.0), (1, 1.0)].into_iter().collect());
+        let mut state: State<VoteCount, f64> =
+            State::new(weights, 0.0, LatestMessages::empty(), 4.0, HashSet::new());
+        state.enable_vote_collector();
+
+        let v0 = &VoteCount::create_vote_message(0, false);
+        let v0_prime = &VoteCount::create_vote_message(0, true);
+
+        state.update(&[v0]);
+        assert!(state.equivocation_proofs().is_empty());
+
+        state.update(&[v0_prime]);
+        let proofs = state.equivocation_proofs();
+        assert_eq!(proofs.len(), 1);
+        assert_eq!(*proofs[0].sender(), 0);
+    }
+
+    #[test]
+    fn vote_collector_quorum_sums_distinct_senders_at_a_round() {
+        let weights = Weights::new(vec![(0, 1.0), (1, 1.0)].into_iter().collect());
+        let mut state: State<VoteCount, f64> =
+            State::new(weights, 0.0, LatestMessages::empty(), 4.0, HashSet::new());
+        state.enable_vote_collector();
+
+        let v0 = &VoteCount::create_vote_message(0, true);
+        state.update(&[v0]);
+
+        let collector = state.vote_collector().unwrap();
+        assert!(!collector.quorum(0, state.validators_weights(), 2.0));
+
+        let v1 = &VoteCount::create_vote_message(1, true);
+        state.update(&[v1]);
+
+        let collector = state.vote_collector().unwrap();
+        assert!(collector.quorum(0, state.validators_weights(), 2.0));
+    }
+
+    #[test]
+    fn update_declines_an_equivocator_that_would_exceed_the_threshold() {
+        let weights = Weights::new(vec![(0, 1.0), (1, 1.0)].into_iter().collect());
+        let mut state: State<VoteCount, f64> =
+            State::new(weights, 0.0, LatestMessages::empty(), 0.0, HashSet::new());
+
+        let v0 = &VoteCount::create_vote_message(0, false);
+        let v0_prime = &VoteCount::create_vote_message(0, true);
+
+        assert!(state.update(&[v0]).is_empty());
+        assert_eq!(state.remaining_fault_tolerance(), 0.0);
+
+        let declined = state.update(&[v0_prime]);
+        assert_eq!(declined, vec![0].into_iter().collect());
+        assert_eq!(
+            state.equivocation_proofs().len(),
+            1,
+            "the attempt is still recorded even though it was declined"
+        );
+        assert_eq!(
+            state.fault_weight(),
+            0.0,
+            "a declined equivocator must not be admitted into the fault-weight tally"
+        );
+    }
+
+    #[test]
+    fn update_admits_an_equivocator_within_the_threshold() {
+        let weights = Weights::new(vec![(0, 1.0), (1, 1.0)].into_iter().collect());
+        let mut state: State<VoteCount, f64> =
+            State::new(weights, 0.0, LatestMessages::empty(), 2.0, HashSet::new());
+
+        let v0 = &VoteCount::create_vote_message(0, false);
+        let v0_prime = &VoteCount::create_vote_message(0, true);
+
+        state.update(&[v0]);
+        let declined = state.update(&[v0_prime]);
+
+        assert!(declined.is_empty());
+        assert_eq!(state.fault_weight(), 1.0);
+        assert_eq!(state.remaining_fault_tolerance(), 1.0);
+    }
+}
@@ -0,0 +1,131 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::ops::Add;
+
+use crate::estimator::Estimator;
+use crate::message::{Message, MisbehaviorProof};
+use crate::util::hash::Hash;
+use crate::util::id::Id;
+use crate::util::weight::WeightUnit;
+use crate::validator::{ValidatorName, Weights};
+
+/// A message's round: the depth of its justification, so every message built directly
+/// on the same justification frontier lands in the same round as its siblings.
+pub type Round = u32;
+
+/// Every message a validator has sent within a single round, keyed by sender so a
+/// second, conflicting message from the same validator is an O(1) lookup away instead
+/// of a scan over the round's entire message set.
+#[derive(Clone, Debug, Default)]
+struct StepCollector<E: Estimator>
+where
+    E::ValidatorName: ValidatorName,
+{
+    votes: HashMap<E::ValidatorName, HashSet<Message<E>>>,
+}
+
+impl<E: Estimator> StepCollector<E>
+where
+    E::ValidatorName: ValidatorName,
+{
+    fn new() -> Self {
+        StepCollector {
+            votes: HashMap::new(),
+        }
+    }
+
+    /// Records `message` as sent by its sender this round, returning a proof of
+    /// misbehavior if it conflicts with a message already recorded from the same
+    /// sender.
+    fn insert(&mut self, message: &Message<E>) -> Option<MisbehaviorProof<E>> {
+        let existing = self
+            .votes
+            .entry(message.sender().clone())
+            .or_insert_with(HashSet::new);
+        let proof = existing
+            .iter()
+            .find_map(|other| other.extract_equivocation_proof(message));
+        existing.insert(message.clone());
+        proof
+    }
+
+    fn senders(&self) -> impl Iterator<Item = &E::ValidatorName> {
+        self.votes.keys()
+    }
+}
+
+/// Round-indexed index over every message a [`crate::validator::State`] admits, trading
+/// `LatestMessages`' flat per-sender view -- which double-vote detection otherwise has
+/// to scan in full -- for an O(log n) lookup by round: two differing messages from the
+/// same validator in the same round are caught the moment the second arrives, without
+/// rescanning the rest of the run. Opt-in: a `State` only pays for this once
+/// [`crate::validator::State::enable_vote_collector`] has been called.
+#[derive(Clone, Debug, Default)]
+pub struct VoteCollector<E: Estimator>
+where
+    E::ValidatorName: ValidatorName,
+{
+    rounds: BTreeMap<Round, StepCollector<E>>,
+    /// Caches each indexed message's own round, so recomputing a later message's depth
+    /// only has to walk back to the nearest already-indexed ancestor instead of the whole
+    /// justification DAG every time.
+    round_by_id: HashMap<Hash, Round>,
+}
+
+impl<E: Estimator> VoteCollector<E>
+where
+    E::ValidatorName: ValidatorName,
+{
+    pub fn new() -> Self {
+        VoteCollector {
+            rounds: BTreeMap::new(),
+            round_by_id: HashMap::new(),
+        }
+    }
+
+    /// A message's actual justification depth: one past the deepest round among its
+    /// justification parents, or round zero for a message with no justification. Unlike
+    /// `justification().len()` -- the count of distinct senders a message cites, which
+    /// plateaus at the validator set size once everyone has voted once -- this keeps
+    /// growing round over round, so double-vote detection and `quorum` stay meaningful
+    /// past that point.
+    fn round_of(&mut self, message: &Message<E>) -> Round {
+        let id = message.id();
+        if let Some(&round) = self.round_by_id.get(&id) {
+            return round;
+        }
+        let round = message
+            .justification()
+            .iter()
+            .map(|parent| self.round_of(parent))
+            .max()
+            .map_or(0, |deepest_parent_round| deepest_parent_round + 1);
+        self.round_by_id.insert(id, round);
+        round
+    }
+
+    /// Indexes `message` under its round, returning a proof of misbehavior if doing so
+    /// catches the sender double-voting within that round.
+    pub fn insert(&mut self, message: &Message<E>) -> Option<MisbehaviorProof<E>> {
+        let round = self.round_of(message);
+        self.rounds
+            .entry(round)
+            .or_insert_with(StepCollector::new)
+            .insert(message)
+    }
+
+    /// Whether the combined weight of every distinct validator recorded at `round`
+    /// meets or exceeds `thr`.
+    pub fn quorum<U>(&self, round: Round, weights: &Weights<E::ValidatorName, U>, thr: U) -> bool
+    where
+        U: WeightUnit + Copy + Add<Output = U> + PartialOrd,
+    {
+        let accumulated = match self.rounds.get(&round) {
+            Some(collector) => collector
+                .senders()
+                .filter_map(|sender| weights.weight(sender).ok())
+                .fold(U::ZERO, |acc, w| acc + w),
+            None => U::ZERO,
+        };
+        accumulated >= thr
+    }
+}
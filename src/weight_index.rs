@@ -0,0 +1,92 @@
+use std::collections::{HashMap, HashSet};
+use std::ops::{Add, Sub};
+
+use crate::blockchain::Block;
+use crate::util::hash::Hash;
+use crate::util::id::Id;
+use crate::util::weight::WeightUnit;
+use crate::validator;
+
+/// Proto-array-style incremental cache of per-block vote weight, turning fork-choice and
+/// estimate queries from full-DAG re-traversals into O(depth) pointer-following.
+///
+/// Caches, for every block reachable from `root`, the running sum of validator weight
+/// whose latest message currently supports it (the block itself or one of its
+/// descendants is that validator's latest vote). [`apply_vote_change`] updates this
+/// incrementally whenever a single validator's latest vote moves from one block to
+/// another: it subtracts the validator's weight along the old vote's ancestor chain and
+/// adds it along the new vote's, rather than recomputing the whole tree, the way
+/// `validator::State::update` would have to without this cache.
+///
+/// [`apply_vote_change`]: #method.apply_vote_change
+pub struct WeightIndex<V: validator::ValidatorName, U> {
+    root: Block<V>,
+    weight: HashMap<Hash, U>,
+    latest_vote: HashMap<V, Block<V>>,
+}
+
+impl<V, U> WeightIndex<V, U>
+where
+    V: validator::ValidatorName,
+    U: WeightUnit + Copy + Add<Output = U> + Sub<Output = U> + PartialOrd,
+{
+    pub fn new(root: Block<V>) -> Self {
+        WeightIndex {
+            root,
+            weight: HashMap::new(),
+            latest_vote: HashMap::new(),
+        }
+    }
+
+    /// The currently cached weight backing `block`, or zero if nothing votes for it (or
+    /// any of its descendants) yet.
+    pub fn weight_of(&self, block: &Block<V>) -> U {
+        self.weight.get(&block.getid()).copied().unwrap_or(U::ZERO)
+    }
+
+    /// Records that `validator` (carrying `validator_weight`) now latest-votes for
+    /// `new_vote`, having previously voted for whatever it last voted for (if anything).
+    /// Only the two affected ancestor chains are touched.
+    pub fn apply_vote_change(&mut self, validator: V, new_vote: Block<V>, validator_weight: U) {
+        if let Some(old_vote) = self.latest_vote.get(&validator).cloned() {
+            Self::walk_ancestors(&old_vote, &mut self.weight, |w| *w = *w - validator_weight);
+        }
+        Self::walk_ancestors(&new_vote, &mut self.weight, |w| *w = *w + validator_weight);
+        self.latest_vote.insert(validator, new_vote);
+    }
+
+    fn walk_ancestors(block: &Block<V>, weight: &mut HashMap<Hash, U>, mut apply: impl FnMut(&mut U)) {
+        let mut current = Some(block.clone());
+        while let Some(b) = current {
+            let entry = weight.entry(b.getid()).or_insert(U::ZERO);
+            apply(entry);
+            current = b.prevblock();
+        }
+    }
+
+    /// Returns the current heaviest-subtree leaf reachable from `root`, in O(depth): at
+    /// each block, follows the child carrying the greatest cached weight (ties broken by
+    /// the child's `Hash`), stopping once a block has no children with positive cached
+    /// weight left. `children` is the same `block -> children` map `Block::parse_blockchains`
+    /// builds from the justification DAG.
+    pub fn best_descendant(&self, children: &HashMap<Block<V>, HashSet<Block<V>>>) -> Block<V> {
+        let mut current = self.root.clone();
+        loop {
+            let next = children.get(&current).and_then(|kids| {
+                kids.iter()
+                    .filter(|child| self.weight_of(child) > U::ZERO)
+                    .max_by(|a, b| {
+                        self.weight_of(a)
+                            .partial_cmp(&self.weight_of(b))
+                            .unwrap_or(std::cmp::Ordering::Equal)
+                            .then_with(|| a.getid().cmp(&b.getid()))
+                    })
+                    .cloned()
+            });
+            match next {
+                Some(child) => current = child,
+                None => return current,
+            }
+        }
+    }
+}
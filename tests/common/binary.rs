@@ -17,10 +17,11 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use casper::estimator::Estimator;
-use casper::justification::LatestMsgsHonest;
-use casper::util::weight::{WeightUnit, Zero};
-use casper::validator;
+use core_cbc_casper::estimator::Estimator;
+use core_cbc_casper::justification::LatestMessagesHonest;
+use core_cbc_casper::message::Message;
+use core_cbc_casper::util::weight::WeightUnit;
+use core_cbc_casper::validator;
 
 type Validator = u32;
 
@@ -59,29 +60,36 @@ impl std::convert::From<&'static str> for Error {
 }
 
 impl Estimator for BoolWrapper {
-    type V = Validator;
+    type ValidatorName = Validator;
     type Error = Error;
 
-    /// Weighted count of the votes contained in the latest messages.
+    /// Weighted count of the votes contained in the latest messages. `WeightUnit`'s
+    /// `PartialOrd` bound is what makes `true_w >= false_w` well-defined for any weight
+    /// type a caller folds with here -- including [`core_cbc_casper::util::weight::Rational256`],
+    /// whose exact arithmetic gives this tie-break a result that doesn't depend on
+    /// message iteration order the way `f64` addition could.
     fn estimate<U: WeightUnit>(
-        latest_msgs: &LatestMsgsHonest<BoolWrapper, Validator>,
-        validators_weights: &validator::Weights<Validator, U>,
+        latest_msgs: &LatestMessagesHonest<Message<Self>>,
+        validators_weights: &validator::Weights<Self::ValidatorName, U>,
     ) -> Result<Self, Self::Error> {
         // loop over all the latest messages
-        let (true_w, false_w) = latest_msgs.iter().fold(
-            (<U as Zero<U>>::ZERO, <U as Zero<U>>::ZERO),
-            |(true_w, false_w), msg| {
-                // get the weight for the validator
-                let validator_weight = validators_weights.weight(msg.sender()).unwrap_or(U::NAN);
+        let (true_w, false_w) =
+            latest_msgs
+                .iter()
+                .fold((U::ZERO, U::ZERO), |(true_w, false_w), msg| {
+                    // get the weight for the validator; a validator missing from the weight
+                    // map contributes no weight, not `U::NAN` -- the old fallback made the
+                    // `true_w >= false_w` tie-break below ill-defined whenever it was hit
+                    let validator_weight =
+                        validators_weights.weight(msg.sender()).unwrap_or(U::ZERO);
 
-                // add the weight to the right accumulator
-                if msg.estimate().0 {
-                    (true_w + validator_weight, false_w)
-                } else {
-                    (true_w, false_w + validator_weight)
-                }
-            },
-        );
+                    // add the weight to the right accumulator
+                    if msg.estimate().0 {
+                        (true_w + validator_weight, false_w)
+                    } else {
+                        (true_w, false_w + validator_weight)
+                    }
+                });
 
         Ok(BoolWrapper(true_w >= false_w))
     }
@@ -0,0 +1,129 @@
+// Core CBC Casper
+// Copyright (C) 2018 - 2020  Coordination Technology Ltd.
+// Authors: pZ4 <pz4@protonmail.ch>,
+//          Lederstrumpf,
+//          h4sh3d <h4sh3d@truelevel.io>
+//          roflolilolmao <q@truelevel.ch>
+//
+// This file is part of Core CBC Casper.
+//
+// Core CBC Casper is free software: you can redistribute it and/or modify it under the terms
+// of the GNU Affero General Public License as published by the Free Software Foundation, either
+// version 3 of the License, or (at your option) any later version.
+//
+// Core CBC Casper is distributed in the hope that it will be useful, but WITHOUT ANY
+// WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License along with the Core CBC
+// Rust Library. If not, see <https://www.gnu.org/licenses/>.
+
+//! Property-based invariants for `Message::from_validator_state`, complementing the
+//! hand-written spot-checks in `src/message.rs`'s own test module by exercising random
+//! mixes of honest and equivocating validators instead of a handful of fixed scenarios.
+
+#![cfg(feature = "integration_test")]
+extern crate core_cbc_casper;
+extern crate proptest;
+
+use std::collections::HashSet;
+
+use proptest::prelude::*;
+
+use core_cbc_casper::justification::{LatestMessages, LatestMessagesHonest};
+use core_cbc_casper::message::Message;
+use core_cbc_casper::validator;
+use core_cbc_casper::VoteCount;
+
+const NUM_VALIDATORS: u32 = 5;
+
+/// A random instruction: either `sender` casts a brand new honest root vote for `vote`,
+/// or `sender` equivocates by casting a second, conflicting root vote.
+#[derive(Clone, Debug)]
+enum Instruction {
+    Vote { sender: u32, vote: bool },
+    Equivocate { sender: u32, vote: bool },
+}
+
+fn instruction_strategy() -> impl Strategy<Value = Instruction> {
+    prop_oneof![
+        (0..NUM_VALIDATORS, any::<bool>())
+            .prop_map(|(sender, vote)| Instruction::Vote { sender, vote }),
+        (0..NUM_VALIDATORS, any::<bool>())
+            .prop_map(|(sender, vote)| Instruction::Equivocate { sender, vote }),
+    ]
+}
+
+proptest! {
+    #[test]
+    fn from_validator_state_invariants(
+        instructions in prop::collection::vec(instruction_strategy(), 0..24),
+        threshold in 0.0f64..(NUM_VALIDATORS as f64),
+    ) {
+        let weights = validator::Weights::new(
+            (0..NUM_VALIDATORS).map(|v| (v, 1.0)).collect(),
+        );
+
+        let mut latest_messages = LatestMessages::empty();
+        let mut equivocators: HashSet<u32> = HashSet::new();
+
+        for instruction in &instructions {
+            let (sender, vote) = match *instruction {
+                Instruction::Vote { sender, vote } => (sender, vote),
+                Instruction::Equivocate { sender, vote } => (sender, vote),
+            };
+            let message = VoteCount::create_vote_message(sender, vote);
+            latest_messages.update(&message);
+            if matches!(instruction, Instruction::Equivocate { .. }) {
+                equivocators.insert(sender);
+            }
+        }
+
+        let latest_messages_honest =
+            LatestMessagesHonest::from_latest_messages(&latest_messages, &equivocators);
+
+        // `LatestMessagesHonest` never includes a known equivocator.
+        for message in latest_messages_honest.iter() {
+            prop_assert!(!equivocators.contains(message.sender()));
+        }
+
+        let accrued_fault_weight = equivocators
+            .iter()
+            .filter_map(|sender| weights.weight(sender).ok())
+            .fold(0.0, |acc, w| acc + w);
+
+        let state = validator::State::new(
+            weights.clone(),
+            accrued_fault_weight,
+            latest_messages.clone(),
+            threshold,
+            equivocators.clone(),
+        );
+
+        let result = Message::from_validator_state(0, &state);
+
+        if latest_messages_honest.is_empty() {
+            // Nothing honest to build an estimate from: must be an error, never a
+            // committed estimate.
+            prop_assert!(result.is_err());
+        } else if accrued_fault_weight > threshold {
+            // Equivocators beyond the fault-weight threshold must never produce a
+            // committed estimate.
+            prop_assert!(result.is_err());
+        } else if let Ok(derived) = &result {
+            // The returned estimate must be consistent with what the honest subset of
+            // latest messages alone would produce.
+            let expected = latest_messages_honest.make_estimate(&weights).unwrap();
+            prop_assert_eq!(derived.estimate().clone(), expected);
+
+            // A derived message's justification never contains two messages from the
+            // same honest sender.
+            let mut seen_senders = HashSet::new();
+            for ancestor in derived.justification().iter() {
+                if !equivocators.contains(ancestor.sender()) {
+                    prop_assert!(seen_senders.insert(ancestor.sender().clone()));
+                }
+            }
+        }
+    }
+}
@@ -23,7 +23,7 @@ extern crate core_cbc_casper;
 extern crate proptest;
 extern crate rand;
 
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::iter;
 use std::iter::FromIterator;
 
@@ -54,7 +54,11 @@ use std::io::Write;
 use std::time::Instant;
 
 mod tools;
-use tools::ChainData;
+use tools::{Certificate, ChainData};
+
+/// How often `safety_oracle_at_height` checkpoints a `Certificate`, mirroring GRANDPA's
+/// periodic (rather than per-block) justification generation.
+const CHECKPOINT_EVERY: u32 = 5;
 
 type ValidatorStatesMap<E> = HashMap<u32, validator::State<E, f64>>;
 
@@ -166,6 +170,222 @@ where
         .collect()
 }
 
+/// A message produced this round but not yet delivered to `recipient`, sitting in a
+/// [`DeliveryQueue`] until `deliver_at_round` arrives.
+///
+/// [`DeliveryQueue`]: struct.DeliveryQueue.html
+struct InFlight<E> {
+    message: Message<E>,
+    sender: u32,
+    recipient: u32,
+    deliver_at_round: u32,
+}
+
+/// Sits between [`create_messages`] and [`add_messages`], replacing their lock-step,
+/// zero-latency, fully-connected delivery with delayed, reordered, partitioned, and
+/// dropped delivery, so the generative tests can exercise liveness and safety-oracle
+/// behavior under realistic asynchrony rather than only the synchronous broadcast
+/// `all_receivers` hard-codes.
+///
+/// [`create_messages`]: fn.create_messages.html
+/// [`add_messages`]: fn.add_messages.html
+struct DeliveryQueue<E> {
+    in_flight: Vec<InFlight<E>>,
+    current_round: u32,
+}
+
+impl<E: Clone> DeliveryQueue<E> {
+    fn new() -> Self {
+        DeliveryQueue {
+            in_flight: Vec::new(),
+            current_round: 0,
+        }
+    }
+
+    /// Enqueues one copy of `message` per recipient in `recipients`, using `rng` (a
+    /// `TestRng` seeded from the run's proptest seed, for reproducibility) to draw a random latency in `latency_rounds`, drop the copy
+    /// outright with probability `drop_probability`, or withhold it for the duration of
+    /// `partition` when `sender` and that recipient fall on opposite sides of the split.
+    #[allow(clippy::too_many_arguments)]
+    fn enqueue(
+        &mut self,
+        message: &Message<E>,
+        sender: u32,
+        recipients: &HashSet<u32>,
+        latency_rounds: std::ops::RangeInclusive<u32>,
+        drop_probability: f64,
+        partition: &Option<(HashSet<u32>, HashSet<u32>)>,
+        rng: &mut TestRng,
+    ) {
+        for &recipient in recipients {
+            if rng.gen_range(0.0, 1.0) < drop_probability {
+                continue;
+            }
+            if let Some((left, right)) = partition {
+                let split = (left.contains(&sender) && right.contains(&recipient))
+                    || (right.contains(&sender) && left.contains(&recipient));
+                if split {
+                    continue;
+                }
+            }
+            let latency = rng.gen_range(*latency_rounds.start(), latency_rounds.end() + 1);
+            self.in_flight.push(InFlight {
+                message: message.clone(),
+                sender,
+                recipient,
+                deliver_at_round: self.current_round + latency,
+            });
+        }
+    }
+
+    /// Removes and returns every entry whose `deliver_at_round` has arrived, grouped back
+    /// into the `(message, validator, recipients)` shape `add_messages` expects, then
+    /// advances `current_round` by one.
+    fn drain_due(&mut self) -> Vec<(Message<E>, u32, HashSet<u32>)>
+    where
+        E: std::hash::Hash + Eq,
+    {
+        let current_round = self.current_round;
+        let (due, pending): (Vec<_>, Vec<_>) = self
+            .in_flight
+            .drain(..)
+            .partition(|entry| entry.deliver_at_round <= current_round);
+        self.in_flight = pending;
+        self.current_round += 1;
+
+        let mut grouped: HashMap<(u32, Message<E>), HashSet<u32>> = HashMap::new();
+        for entry in due {
+            grouped
+                .entry((entry.sender, entry.message))
+                .or_insert_with(HashSet::new)
+                .insert(entry.recipient);
+        }
+        grouped
+            .into_iter()
+            .map(|((sender, message), recipients)| (message, sender, recipients))
+            .collect()
+    }
+}
+
+/// Like [`create_messages`], but `byzantine` names validators that equivocate this round
+/// instead of casting a single honest message: each one still derives a primary message
+/// from its updated validator state exactly as `create_messages` would, but additionally
+/// forks a second message sharing that same justification, with its estimate replaced by
+/// `fork_estimate`. Neither message depends on the other, so they form a genuine
+/// equivocation once both are registered in `latests_messages` and delivered.
+///
+/// Unlike `create_messages`, this also lifts the `_ => unimplemented!()` restriction: a
+/// validator's own latest-message set may legitimately hold more than one message (it may
+/// itself be an equivocator from an earlier round), so the delta is computed against all
+/// of them, not just a single one.
+///
+/// [`create_messages`]: fn.create_messages.html
+fn create_messages_with_byzantine<E>(
+    state: &mut ValidatorStatesMap<E>,
+    byzantine: &HashSet<u32>,
+    fork_estimate: fn(&E) -> E,
+    validators_recipients_data: Vec<(u32, HashSet<u32>)>,
+) -> Vec<(Message<E>, u32, HashSet<u32>)>
+where
+    E: Estimator<ValidatorName = u32>,
+{
+    validators_recipients_data
+        .into_iter()
+        .flat_map(|(validator, recipients)| {
+            let latest: HashSet<Message<E>> = state[&validator]
+                .latests_messages()
+                .iter()
+                .fold(HashSet::new(), |acc, (_, lms)| {
+                    acc.union(&lms).cloned().collect()
+                });
+
+            let latest_delta: HashSet<Message<E>> =
+                match state[&validator].latests_messages().get(&validator) {
+                    Some(messages) if !messages.is_empty() => latest
+                        .iter()
+                        .filter(|latest_message| {
+                            !messages
+                                .iter()
+                                .any(|message| message.justification().contains(latest_message))
+                        })
+                        .cloned()
+                        .collect(),
+                    _ => latest,
+                };
+
+            let mut validator_state = state[&validator].clone();
+            for message in latest_delta.iter() {
+                validator_state.update(&[&message]);
+            }
+            let primary = Message::from_validator_state(validator, &validator_state).unwrap();
+
+            let mut produced = vec![primary.clone()];
+            if byzantine.contains(&validator) {
+                let forked_estimate = fork_estimate(primary.estimate());
+                produced.push(Message::new(
+                    validator,
+                    primary.justification().clone(),
+                    forked_estimate,
+                ));
+            }
+
+            state.insert(validator, validator_state);
+            let latests_messages = state.get_mut(&validator).unwrap().latests_messages_as_mut();
+            for message in produced.iter() {
+                latests_messages.update(message);
+            }
+
+            produced
+                .into_iter()
+                .map(move |message| (message, validator, recipients.clone()))
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// Message-producer strategy that, like [`arbitrary_in_set`], picks one validator per
+/// step uniformly at random; the returned validator equivocates that round whenever it
+/// belongs to `byzantine`, the designated Byzantine subset, rather than always casting a
+/// single honest message. Pairs with [`create_messages_with_byzantine`], which is what
+/// actually decides whether the picked validator forks a second message.
+///
+/// [`arbitrary_in_set`]: fn.arbitrary_in_set.html
+/// [`create_messages_with_byzantine`]: fn.create_messages_with_byzantine.html
+fn byzantine_producer(values: &mut Vec<u32>) -> BoxedStrategy<HashSet<u32>> {
+    arbitrary_in_set(values)
+}
+
+/// Like [`message_events`], but routes through [`create_messages_with_byzantine`] so the
+/// validators named in `byzantine` equivocate instead of casting a single honest message.
+///
+/// [`message_events`]: fn.message_events.html
+/// [`create_messages_with_byzantine`]: fn.create_messages_with_byzantine.html
+fn message_events_with_byzantine<E>(
+    state: ValidatorStatesMap<E>,
+    byzantine: HashSet<u32>,
+    fork_estimate: fn(&E) -> E,
+    validator_receiver_strategy: BoxedStrategy<HashMap<u32, HashSet<u32>>>,
+) -> BoxedStrategy<Result<ValidatorStatesMap<E>, &'static str>>
+where
+    E: Estimator<ValidatorName = u32> + 'static,
+{
+    (validator_receiver_strategy, Just(state))
+        .prop_map(move |(map_validator_receivers, mut state)| {
+            let vec_validators_recipients_data = map_validator_receivers.into_iter().collect();
+            let vec_data = create_messages_with_byzantine(
+                &mut state,
+                &byzantine,
+                fork_estimate,
+                vec_validators_recipients_data,
+            );
+            match add_messages(&mut state, vec_data) {
+                Ok(()) => Ok(state),
+                Err(e) => Err(e),
+            }
+        })
+        .boxed()
+}
+
 /// Validator strategy that selects one validator at each step, in a round robin manner.
 fn round_robin(values: &mut Vec<u32>) -> BoxedStrategy<HashSet<u32>> {
     let value = values.pop().unwrap();
@@ -191,6 +411,45 @@ fn parallel_arbitrary_in_set(values: &mut Vec<u32>) -> BoxedStrategy<HashSet<u32
         .boxed()
 }
 
+/// Message-producer strategy factory modeling leader-based block production: given each
+/// validator's proposer `weights`, returns a strategy that draws a single seeded value in
+/// `[0, total_weight)` each round and selects whichever validator's cumulative-weight
+/// interval contains it, so heavier validators propose proportionally more often than a
+/// uniform pick from [`arbitrary_in_set`] would. Validators missing from `weights` default
+/// to weight `1.0`.
+///
+/// [`arbitrary_in_set`]: fn.arbitrary_in_set.html
+fn leader_sequence(weights: HashMap<u32, f64>) -> impl Fn(&mut Vec<u32>) -> BoxedStrategy<HashSet<u32>> {
+    move |values: &mut Vec<u32>| {
+        let cumulative_weights: Vec<(u32, f64)> = values
+            .iter()
+            .scan(0.0, |running_total, validator| {
+                *running_total += weights.get(validator).copied().unwrap_or(1.0);
+                Some((*validator, *running_total))
+            })
+            .collect();
+        let total_weight = cumulative_weights
+            .last()
+            .map(|(_, cumulative)| *cumulative)
+            .unwrap_or(0.0);
+
+        (0u32..1_000_000)
+            .prop_map(move |draw| {
+                let target = total_weight * (f64::from(draw) / 1_000_000.0);
+                let leader = cumulative_weights
+                    .iter()
+                    .find(|(_, cumulative)| target < *cumulative)
+                    .or_else(|| cumulative_weights.last())
+                    .map(|(validator, _)| *validator)
+                    .expect("leader_sequence requires at least one validator");
+                let mut hashset = HashSet::new();
+                hashset.insert(leader);
+                hashset
+            })
+            .boxed()
+    }
+}
+
 /// Receiver strategy that picks between 0 and n receivers at random, n being the number of
 /// validators.
 fn some_receivers(_validator: u32, possible_validators: &[u32], rng: &mut TestRng) -> HashSet<u32> {
@@ -253,7 +512,8 @@ fn full_consensus<E>(
     _height_of_oracle: u32,
     _vec_data: &mut Vec<ChainData>,
     _chain_id: u32,
-    _received_messages: &mut HashMap<u32, HashSet<Block<ValidatorNameBlockData<u32>>>>,
+    _received_messages: &mut HashMap<u32, HashMap<Block<ValidatorNameBlockData<u32>>, u32>>,
+    _round: u32,
 ) -> bool
 where
     E: Estimator<ValidatorName = u32>,
@@ -274,13 +534,14 @@ where
 
 /// Performs safety oracle search and adds information to the data parameter.
 /// Info added: consensus_height and longest_chain.
-/// Return true if some safety oracle is detected at max_height_of_oracle.
+/// Returns the finalized block and its detecting clique's total weight once a safety
+/// oracle is detected at max_height_of_oracle, None otherwise.
 /// The threshold for the safety oracle is set to half of the sum of the validators weights.
 fn get_data_from_state(
     validator_state: &validator::State<Block<ValidatorNameBlockData<u32>>, f64>,
     max_height_of_oracle: u32,
     data: &mut ChainData,
-) -> bool {
+) -> Option<(Block<ValidatorNameBlockData<u32>>, f64, BTreeSet<u32>, f64)> {
     let latest_messages_honest = LatestMessagesHonest::from_latest_messages(
         validator_state.latests_messages(),
         &validator_state.equivocators(),
@@ -291,6 +552,7 @@ fn get_data_from_state(
         tools::get_height_selected_chain(&latest_messages_honest, validator_state);
 
     let mut consensus_height: i64 = -1;
+    let mut finalized: Option<(Block<ValidatorNameBlockData<u32>>, f64, BTreeSet<u32>, f64)> = None;
 
     let safety_threshold = validator_state.validators_weights().sum_all_weights() / 2.0;
 
@@ -298,16 +560,38 @@ fn get_data_from_state(
     genesis_blocks.insert(Block::new(None, ValidatorNameBlockData::new(0)));
 
     for height in 0..=max_height_of_oracle {
+        let mut satisfying_block_and_weight = None;
         let is_local_consensus_satisfied = genesis_blocks.iter().cloned().any(|genesis_block| {
             // returns set of btreeset? basically the cliques; if
             // the set is not empty, there is at least one clique
-            Block::safety_oracles(
-                genesis_block,
+            let cliques = Block::safety_oracles(
+                genesis_block.clone(),
                 &latest_messages_honest,
                 &HashSet::new(),
                 safety_threshold,
                 validator_state.validators_weights(),
-            ) != HashSet::new()
+            );
+            if cliques == HashSet::new() {
+                false
+            } else {
+                let clique_weight: f64 = cliques
+                    .iter()
+                    .flat_map(|clique| clique.iter())
+                    .collect::<HashSet<_>>()
+                    .into_iter()
+                    .filter_map(|validator| validator_state.validators_weights().weight(validator).ok())
+                    .sum();
+                // the certificate names a single concrete clique -- the first one found --
+                // rather than the union `clique_weight` above is computed over.
+                let certified_clique = cliques.iter().next().cloned().unwrap_or_default();
+                let certified_weight: f64 = certified_clique
+                    .iter()
+                    .filter_map(|validator| validator_state.validators_weights().weight(validator).ok())
+                    .sum();
+                satisfying_block_and_weight =
+                    Some((genesis_block, clique_weight, certified_clique, certified_weight));
+                true
+            }
         });
 
         consensus_height = if is_local_consensus_satisfied {
@@ -315,6 +599,9 @@ fn get_data_from_state(
         } else {
             break;
         };
+        if height == max_height_of_oracle {
+            finalized = satisfying_block_and_weight;
+        }
 
         let genesis_blocks_children = genesis_blocks
             .iter()
@@ -333,18 +620,24 @@ fn get_data_from_state(
 
     data.consensus_height = consensus_height;
     data.longest_chain = height_selected_chain;
-    is_consensus_satisfied
+    if is_consensus_satisfied {
+        finalized
+    } else {
+        None
+    }
 }
 
 /// Returns true if at least a safety oracle for a block at height_of_oracle
 /// adds a new data to vec_data for each new message that is sent.
-/// Uses received_messages to take note of which validator received which messages
+/// Uses received_messages to take note of which validator first saw which message, at
+/// which round, so a detected safety oracle's finality latency can be derived.
 fn safety_oracle_at_height(
     state: &ValidatorStatesMap<Block<ValidatorNameBlockData<u32>>>,
     height_of_oracle: u32,
     vec_data: &mut Vec<ChainData>,
     chain_id: u32,
-    received_messages: &mut HashMap<u32, HashSet<Block<ValidatorNameBlockData<u32>>>>,
+    received_messages: &mut HashMap<u32, HashMap<Block<ValidatorNameBlockData<u32>>, u32>>,
+    round: u32,
 ) -> bool {
     state.iter().for_each(|(id, validator_state)| {
         for (_, messages) in validator_state.latests_messages().iter() {
@@ -352,15 +645,55 @@ fn safety_oracle_at_height(
                 received_messages
                     .get_mut(id)
                     .unwrap()
-                    .insert(Block::from(message));
+                    .entry(Block::from(message))
+                    .or_insert(round);
             }
         }
     });
     state.iter().any(|(validator_id, validator_state)| {
         let mut data = ChainData::new(chain_id, state.len() as u32, *validator_id, 0, 0, 0);
-        let is_consensus_satisfied =
-            get_data_from_state(validator_state, height_of_oracle, &mut data);
+        let finalized = get_data_from_state(validator_state, height_of_oracle, &mut data);
         data.nb_messages = received_messages.get(validator_id).unwrap().len();
+        let is_consensus_satisfied = finalized.is_some();
+        if let Some((block, clique_weight, clique, certified_weight)) = finalized {
+            let first_seen_round = received_messages
+                .values()
+                .filter_map(|seen| seen.get(&block))
+                .min()
+                .copied()
+                .unwrap_or(round);
+            data.record_finality(round.saturating_sub(first_seen_round), clique_weight);
+
+            if round % CHECKPOINT_EVERY == 0 {
+                let safety_threshold = validator_state.validators_weights().sum_all_weights() / 2.0;
+                let messages = clique
+                    .iter()
+                    .flat_map(|member| {
+                        validator_state
+                            .latests_messages()
+                            .get(member)
+                            .into_iter()
+                            .flatten()
+                            .cloned()
+                    })
+                    .collect();
+                let certificate = Certificate::new(
+                    chain_id,
+                    round,
+                    height_of_oracle,
+                    block,
+                    clique,
+                    certified_weight,
+                    safety_threshold,
+                    messages,
+                );
+                debug_assert!(
+                    certificate.verify(validator_state.validators_weights()),
+                    "a freshly built certificate must verify against its own clique",
+                );
+                data.attach_certificate(certificate);
+            }
+        }
         vec_data.push(data);
         is_consensus_satisfied
     })
@@ -410,7 +743,8 @@ where
         u32,
         &mut Vec<ChainData>,
         u32,
-        &mut HashMap<u32, HashSet<Block<ValidatorNameBlockData<u32>>>>,
+        &mut HashMap<u32, HashMap<Block<ValidatorNameBlockData<u32>>, u32>>,
+        u32,
     ) -> bool,
 {
     (
@@ -427,7 +761,7 @@ where
             let mut validators: Vec<u32> = (0..votes.len() as u32).collect();
             let mut received_messages = validators
                 .iter()
-                .map(|validator| (*validator, HashSet::new()))
+                .map(|validator| (*validator, HashMap::new()))
                 .collect();
 
             let weights: Vec<f64> = iter::repeat(1.0).take(votes.len() as usize).collect();
@@ -485,6 +819,7 @@ where
             // both variable exist to retain the last unlazified result in the chain
             let mut have_consensus = false;
             let mut no_err = true;
+            let mut round: u32 = 0;
 
             let mut start = Instant::now();
             let mut timestamp_file = OpenOptions::new()
@@ -508,6 +843,8 @@ where
                 writeln!(timestamp_file, "{:?}", start.elapsed().subsec_micros()).unwrap();
 
                 start = Instant::now();
+                let this_round = round;
+                round += 1;
                 match (state, no_err) {
                     (Ok(st), true) => {
                         if have_consensus {
@@ -519,6 +856,7 @@ where
                                 &mut vec_data,
                                 chain_id,
                                 &mut received_messages,
+                                this_round,
                             ) {
                                 have_consensus = true
                             }
@@ -542,111 +880,653 @@ where
         .boxed()
 }
 
-fn arbitrary_blockchain() -> BoxedStrategy<Block<ValidatorNameBlockData<u32>>> {
-    let genesis_block = Block::new(None, ValidatorNameBlockData::new(0));
-    Just(genesis_block).boxed()
+/// Weight strategy that assigns every validator the same weight `1.0`, matching the
+/// uniform distribution [`chain`] itself hard-codes.
+///
+/// [`chain`]: fn.chain.html
+fn uniform_weights(validator_count: usize) -> BoxedStrategy<Vec<f64>> {
+    Just(iter::repeat(1.0).take(validator_count).collect()).boxed()
 }
 
-#[test]
-fn blockchain() {
-    let mut config = Config::with_cases(1);
-    config.source_file = Some("tests/generative_tests.rs");
-
-    for chain_id in 0..10 {
-        // TestRunners run only N times when using Config::with_cases(N);
-        // so we have to create a new runner with said config each time we want
-        // to simulate a new blockchain.
-        // We could increase N but chain_id would be the same for each run and overwrite
-        // the blockhain_test_n.log
-        // As of 0.9.2, it is not possible to get the current run index for a runner in order
-        // to replace the chain_id with something more elegant.
-        let mut runner = TestRunner::new(config.clone());
+/// Weight strategy splitting validators into a heavy third and a light two-thirds: each
+/// heavy validator draws a weight in `[5.0, 10.0)`, each light validator in `[0.1, 1.0)`.
+/// Exercises `get_data_from_state`'s `sum_all_weights() / 2.0` safety threshold against a
+/// skewed distribution instead of `chain`'s uniform one.
+fn skewed_weights(validator_count: usize) -> BoxedStrategy<Vec<f64>> {
+    let heavy_count = (validator_count / 3).max(1);
+    (
+        prop::collection::vec(5.0f64..10.0, heavy_count),
+        prop::collection::vec(0.1f64..1.0, validator_count - heavy_count.min(validator_count)),
+    )
+        .prop_map(|(heavy, light)| heavy.into_iter().chain(light).collect())
+        .boxed()
+}
 
-        // truncate if the file already exists
-        let output_file = OpenOptions::new()
-            .create(true)
-            .truncate(true)
-            .write(true)
-            .open(format!("blockchain_test_{}.log", chain_id))
-            .unwrap();
-        // close file handle with truncate option
-        drop(output_file);
+/// Weight strategy placing just under a third of the total weight on validators
+/// `0..colluding_count`, the rest split evenly across the remaining validators. Combined
+/// with [`chain_byzantine`]'s `byzantine` set naming the same validators, this puts an
+/// equivocating coalition right at the edge of (but below) the classic BFT tolerance
+/// bound, so the safety oracle should still eventually be detected.
+///
+/// [`chain_byzantine`]: fn.chain_byzantine.html
+fn adversarial_weights_under_threshold(validator_count: usize) -> BoxedStrategy<Vec<f64>> {
+    let colluding_count = (validator_count / 3).max(1).min(validator_count - 1).max(1);
+    let honest_count = validator_count - colluding_count;
+    // total honest weight is slightly more than twice the colluding weight, i.e. colluding
+    // holds just under (rather than at) 1/3 of the total.
+    let colluding_weight = 1.0;
+    let honest_weight = 2.01 * colluding_weight * colluding_count as f64 / honest_count as f64;
+    Just(
+        iter::repeat(colluding_weight)
+            .take(colluding_count)
+            .chain(iter::repeat(honest_weight).take(honest_count))
+            .collect(),
+    )
+    .boxed()
+}
 
-        runner
-            .run(
-                &chain(
-                    arbitrary_blockchain(),
-                    6,
-                    arbitrary_in_set,
-                    all_receivers,
-                    safety_oracle_at_height,
-                    4,
-                    chain_id,
-                ),
-                |chain| {
-                    chain.iter().for_each(|state| {
-                        let state = state.as_ref().unwrap();
-                        let mut output_file = OpenOptions::new()
-                            .create(true)
-                            .append(true)
-                            .write(true)
-                            .open(format!("blockchain_test_{}.log", chain_id))
-                            .unwrap();
-                        writeln!(
-                            output_file,
-                            "{{lms: {:?},",
-                            state
-                                .iter()
-                                .map(|(_, validator_state)| validator_state.latests_messages())
-                                .collect::<Vec<_>>()
-                        )
-                        .unwrap();
-                        writeln!(output_file, "validatorcount: {:?},", state.keys().len()).unwrap();
-                        writeln!(output_file, "clqs: ").unwrap();
-                        writeln!(output_file, "{:?}}},", clique_collection(state.clone())).unwrap();
-                    });
-                    Ok(())
-                },
+/// Like [`chain`], but validator weights are drawn from `weight_strategy` instead of
+/// `chain`'s hard-coded uniform `1.0`, so generative runs can sample non-uniform or
+/// adversarial distributions -- a few heavy validators plus many light ones, or a
+/// colluding subset weighted right up against the fault-tolerance bound -- rather than
+/// only ever testing the safety oracle against an even split.
+///
+/// [`chain`]: fn.chain.html
+#[allow(clippy::too_many_arguments)]
+fn chain_weighted<E: 'static, F: 'static, W: 'static, H: 'static>(
+    consensus_value_strategy: BoxedStrategy<E>,
+    validator_max_count: usize,
+    weight_strategy: W,
+    message_producer_strategy: F,
+    message_receiver_strategy: fn(u32, &[u32], &mut TestRng) -> HashSet<u32>,
+    consensus_satisfied: H,
+    consensus_satisfied_value: u32,
+    chain_id: u32,
+) -> BoxedStrategy<Vec<Result<ValidatorStatesMap<E>, &'static str>>>
+where
+    E: Estimator<ValidatorName = u32>,
+    F: Fn(&mut Vec<u32>) -> BoxedStrategy<HashSet<u32>>,
+    W: Fn(usize) -> BoxedStrategy<Vec<f64>>,
+    H: Fn(
+        &ValidatorStatesMap<E>,
+        u32,
+        &mut Vec<ChainData>,
+        u32,
+        &mut HashMap<u32, HashMap<Block<ValidatorNameBlockData<u32>>, u32>>,
+        u32,
+    ) -> bool,
+{
+    (
+        prop::sample::select((1..validator_max_count).collect::<Vec<usize>>()),
+        any::<[u8; 32]>(),
+    )
+        .prop_flat_map(move |(validators, seed)| {
+            (
+                prop::collection::vec(consensus_value_strategy.clone(), validators),
+                weight_strategy(validators),
+                Just(seed),
             )
-            .unwrap();
-    }
-}
+        })
+        .prop_map(move |(votes, weights, seed)| {
+            let mut validators: Vec<u32> = (0..votes.len() as u32).collect();
+            let mut received_messages = validators
+                .iter()
+                .map(|validator| (*validator, HashMap::new()))
+                .collect();
 
-proptest! {
-    #![proptest_config(Config::with_cases(30))]
-    #[test]
-    fn round_robin_vote_count(
-        ref chain in chain(
-            VoteCount::arbitrary(),
-            5,
-            round_robin,
-            all_receivers,
-            full_consensus,
-            0,
-            0,
-        ),
-    ) {
-        assert_eq!(
-            chain.last().unwrap().as_ref().unwrap_or(&HashMap::new()).keys().len(),
-            chain.len(),
-            "round robin with n validators should converge in n messages",
-        );
-    }
-}
+            let validators_weights =
+                validator::Weights::new(validators.iter().cloned().zip(weights).collect());
 
-fn boolwrapper_gen() -> BoxedStrategy<BoolWrapper> {
-    any::<bool>().prop_map(BoolWrapper::new).boxed()
-}
+            let mut state = Ok(validators
+                .iter()
+                .map(|validator| {
+                    let mut justification = Justification::empty();
+                    let message = Message::new(
+                        *validator,
+                        justification.clone(),
+                        votes[*validator as usize].clone(),
+                    );
+                    justification.insert(message);
+                    (
+                        *validator,
+                        validator::State::new(
+                            validators_weights.clone(),
+                            0.0,
+                            LatestMessages::from(&justification),
+                            0.0,
+                            HashSet::new(),
+                        ),
+                    )
+                })
+                .collect());
 
-fn integerwrapper_gen() -> BoxedStrategy<IntegerWrapper> {
-    any::<u32>().prop_map(IntegerWrapper::new).boxed()
-}
+            let mut runner = TestRunner::new_with_rng(
+                ProptestConfig::default(),
+                TestRng::from_seed(RngAlgorithm::ChaCha, &seed),
+            );
 
-proptest! {
-    #![proptest_config(Config::with_cases(30))]
-    #[test]
-    fn round_robin_binary(
-        ref chain in chain(
+            let chain = iter::repeat_with(|| {
+                let validator_strategy = message_producer_strategy(&mut validators);
+                let receiver_strategy = create_receiver_strategy(
+                    &validators,
+                    validator_strategy,
+                    message_receiver_strategy,
+                );
+
+                match state.clone() {
+                    Ok(st) => {
+                        state = message_events(st, receiver_strategy)
+                            .new_tree(&mut runner)
+                            .unwrap()
+                            .current();
+                        state.clone()
+                    }
+                    Err(e) => Err(e),
+                }
+            });
+            let mut have_consensus = false;
+            let mut no_err = true;
+            let mut round: u32 = 0;
+
+            let mut vec_data = vec![];
+
+            let vec = Vec::from_iter(chain.take_while(|state| {
+                let this_round = round;
+                round += 1;
+                match (state, no_err) {
+                    (Ok(st), true) => {
+                        if have_consensus {
+                            false
+                        } else {
+                            if consensus_satisfied(
+                                st,
+                                consensus_satisfied_value,
+                                &mut vec_data,
+                                chain_id,
+                                &mut received_messages,
+                                this_round,
+                            ) {
+                                have_consensus = true
+                            }
+                            true
+                        }
+                    }
+                    (Err(_), true) => {
+                        no_err = false;
+                        true
+                    }
+                    (_, false) => false,
+                }
+            }));
+
+            vec
+        })
+        .boxed()
+}
+
+/// Like [`chain`], but routes message production through
+/// [`message_events_with_byzantine`] every step, so `byzantine` validators equivocate
+/// (via `fork_estimate`) instead of casting a single honest message each round. Used to
+/// check that `consensus_satisfied` (e.g. `safety_oracle_at_height`, which feeds
+/// `latests_messages`' equivocators into `LatestMessagesHonest::from_latest_messages`)
+/// still converges despite `byzantine`'s accrued fault weight.
+///
+/// [`chain`]: fn.chain.html
+/// [`message_events_with_byzantine`]: fn.message_events_with_byzantine.html
+#[allow(clippy::too_many_arguments)]
+fn chain_byzantine<E: 'static, F: 'static, H: 'static>(
+    consensus_value_strategy: BoxedStrategy<E>,
+    validator_max_count: usize,
+    message_producer_strategy: F,
+    message_receiver_strategy: fn(u32, &[u32], &mut TestRng) -> HashSet<u32>,
+    consensus_satisfied: H,
+    consensus_satisfied_value: u32,
+    chain_id: u32,
+    byzantine: HashSet<u32>,
+    fork_estimate: fn(&E) -> E,
+) -> BoxedStrategy<Vec<Result<ValidatorStatesMap<E>, &'static str>>>
+where
+    E: Estimator<ValidatorName = u32>,
+    F: Fn(&mut Vec<u32>) -> BoxedStrategy<HashSet<u32>>,
+    H: Fn(
+        &ValidatorStatesMap<E>,
+        u32,
+        &mut Vec<ChainData>,
+        u32,
+        &mut HashMap<u32, HashMap<Block<ValidatorNameBlockData<u32>>, u32>>,
+        u32,
+    ) -> bool,
+{
+    (
+        prop::sample::select((1..validator_max_count).collect::<Vec<usize>>()),
+        any::<[u8; 32]>(),
+    )
+        .prop_flat_map(move |(validators, seed)| {
+            (
+                prop::collection::vec(consensus_value_strategy.clone(), validators),
+                Just(seed),
+            )
+        })
+        .prop_map(move |(votes, seed)| {
+            let mut validators: Vec<u32> = (0..votes.len() as u32).collect();
+            let mut received_messages = validators
+                .iter()
+                .map(|validator| (*validator, HashMap::new()))
+                .collect();
+
+            let weights: Vec<f64> = iter::repeat(1.0).take(votes.len() as usize).collect();
+
+            let validators_weights =
+                validator::Weights::new(validators.iter().cloned().zip(weights).collect());
+
+            let mut state = Ok(validators
+                .iter()
+                .map(|validator| {
+                    let mut justification = Justification::empty();
+                    let message = Message::new(
+                        *validator,
+                        justification.clone(),
+                        votes[*validator as usize].clone(),
+                    );
+                    justification.insert(message);
+                    (
+                        *validator,
+                        validator::State::new(
+                            validators_weights.clone(),
+                            0.0,
+                            LatestMessages::from(&justification),
+                            0.0,
+                            HashSet::new(),
+                        ),
+                    )
+                })
+                .collect());
+
+            let mut runner = TestRunner::new_with_rng(
+                ProptestConfig::default(),
+                TestRng::from_seed(RngAlgorithm::ChaCha, &seed),
+            );
+
+            let chain = iter::repeat_with(|| {
+                let validator_strategy = message_producer_strategy(&mut validators);
+                let receiver_strategy = create_receiver_strategy(
+                    &validators,
+                    validator_strategy,
+                    message_receiver_strategy,
+                );
+
+                match state.clone() {
+                    Ok(st) => {
+                        state = message_events_with_byzantine(
+                            st,
+                            byzantine.clone(),
+                            fork_estimate,
+                            receiver_strategy,
+                        )
+                        .new_tree(&mut runner)
+                        .unwrap()
+                        .current();
+                        state.clone()
+                    }
+                    Err(e) => Err(e),
+                }
+            });
+            let mut have_consensus = false;
+            let mut no_err = true;
+            let mut round: u32 = 0;
+
+            let mut vec_data = vec![];
+
+            let vec = Vec::from_iter(chain.take_while(|state| {
+                let this_round = round;
+                round += 1;
+                match (state, no_err) {
+                    (Ok(st), true) => {
+                        if have_consensus {
+                            false
+                        } else {
+                            if consensus_satisfied(
+                                st,
+                                consensus_satisfied_value,
+                                &mut vec_data,
+                                chain_id,
+                                &mut received_messages,
+                                this_round,
+                            ) {
+                                have_consensus = true
+                            }
+                            true
+                        }
+                    }
+                    (Err(_), true) => {
+                        no_err = false;
+                        true
+                    }
+                    (_, false) => false,
+                }
+            }));
+
+            vec
+        })
+        .boxed()
+}
+
+/// Like [`chain`], but produced messages pass through a [`DeliveryQueue`] instead of
+/// `add_messages` delivering them the instant they're created, so each round only
+/// processes whatever delivery latency, drops, and `partition` have let through so far.
+///
+/// [`chain`]: fn.chain.html
+/// [`DeliveryQueue`]: struct.DeliveryQueue.html
+#[allow(clippy::too_many_arguments)]
+fn chain_async<E: 'static, F: 'static, H: 'static>(
+    consensus_value_strategy: BoxedStrategy<E>,
+    validator_max_count: usize,
+    message_producer_strategy: F,
+    message_receiver_strategy: fn(u32, &[u32], &mut TestRng) -> HashSet<u32>,
+    consensus_satisfied: H,
+    consensus_satisfied_value: u32,
+    chain_id: u32,
+    latency_rounds: std::ops::RangeInclusive<u32>,
+    drop_probability: f64,
+    partition: Option<(HashSet<u32>, HashSet<u32>)>,
+    rounds: u32,
+) -> BoxedStrategy<Vec<Result<ValidatorStatesMap<E>, &'static str>>>
+where
+    E: Estimator<ValidatorName = u32> + std::hash::Hash + Eq,
+    F: Fn(&mut Vec<u32>) -> BoxedStrategy<HashSet<u32>>,
+    H: Fn(
+        &ValidatorStatesMap<E>,
+        u32,
+        &mut Vec<ChainData>,
+        u32,
+        &mut HashMap<u32, HashMap<Block<ValidatorNameBlockData<u32>>, u32>>,
+        u32,
+    ) -> bool,
+{
+    (
+        prop::sample::select((1..validator_max_count).collect::<Vec<usize>>()),
+        any::<[u8; 32]>(),
+    )
+        .prop_flat_map(move |(validators, seed)| {
+            (
+                prop::collection::vec(consensus_value_strategy.clone(), validators),
+                Just(seed),
+            )
+        })
+        .prop_map(move |(votes, seed)| {
+            let mut validators: Vec<u32> = (0..votes.len() as u32).collect();
+            let mut received_messages = validators
+                .iter()
+                .map(|validator| (*validator, HashMap::new()))
+                .collect();
+
+            let weights: Vec<f64> = iter::repeat(1.0).take(votes.len() as usize).collect();
+
+            let validators_weights =
+                validator::Weights::new(validators.iter().cloned().zip(weights).collect());
+
+            let mut state: ValidatorStatesMap<E> = validators
+                .iter()
+                .map(|validator| {
+                    let mut justification = Justification::empty();
+                    let message = Message::new(
+                        *validator,
+                        justification.clone(),
+                        votes[*validator as usize].clone(),
+                    );
+                    justification.insert(message);
+                    (
+                        *validator,
+                        validator::State::new(
+                            validators_weights.clone(),
+                            0.0,
+                            LatestMessages::from(&justification),
+                            0.0,
+                            HashSet::new(),
+                        ),
+                    )
+                })
+                .collect();
+
+            let mut runner = TestRunner::new_with_rng(
+                ProptestConfig::default(),
+                TestRng::from_seed(RngAlgorithm::ChaCha, &seed),
+            );
+            // A second TestRng instance, seeded from the same seed, drives DeliveryQueue's
+            // latency/drop rolls, kept separate from `runner`'s so adding or removing calls to one
+            // doesn't perturb the other's sequence.
+            let mut network_rng = TestRng::from_seed(RngAlgorithm::ChaCha, &seed);
+
+            let mut queue: DeliveryQueue<E> = DeliveryQueue::new();
+            let mut have_consensus = false;
+            let mut no_err = true;
+            let mut vec_data = vec![];
+
+            let vec: Vec<Result<ValidatorStatesMap<E>, &'static str>> = (0..rounds)
+                .map_while(|round| {
+                    if have_consensus || !no_err {
+                        return None;
+                    }
+
+                    let validator_strategy = message_producer_strategy(&mut validators);
+                    let receiver_strategy = create_receiver_strategy(
+                        &validators,
+                        validator_strategy,
+                        message_receiver_strategy,
+                    );
+                    let map_validator_receivers = receiver_strategy
+                        .new_tree(&mut runner)
+                        .unwrap()
+                        .current();
+                    let vec_validators_recipients_data =
+                        map_validator_receivers.into_iter().collect();
+                    let produced = create_messages(&mut state, vec_validators_recipients_data);
+                    for (message, sender, recipients) in &produced {
+                        queue.enqueue(
+                            message,
+                            *sender,
+                            recipients,
+                            latency_rounds.clone(),
+                            drop_probability,
+                            &partition,
+                            &mut network_rng,
+                        );
+                    }
+
+                    let due = queue.drain_due();
+                    match add_messages(&mut state, due) {
+                        Ok(()) => {
+                            if consensus_satisfied(
+                                &state,
+                                consensus_satisfied_value,
+                                &mut vec_data,
+                                chain_id,
+                                &mut received_messages,
+                                round,
+                            ) {
+                                have_consensus = true;
+                            }
+                            Some(Ok(state.clone()))
+                        }
+                        Err(e) => {
+                            no_err = false;
+                            Some(Err(e))
+                        }
+                    }
+                })
+                .collect();
+
+            vec
+        })
+        .boxed()
+}
+
+fn arbitrary_blockchain() -> BoxedStrategy<Block<ValidatorNameBlockData<u32>>> {
+    let genesis_block = Block::new(None, ValidatorNameBlockData::new(0));
+    Just(genesis_block).boxed()
+}
+
+#[test]
+fn blockchain() {
+    let mut config = Config::with_cases(1);
+    config.source_file = Some("tests/generative_tests.rs");
+
+    for chain_id in 0..10 {
+        // TestRunners run only N times when using Config::with_cases(N);
+        // so we have to create a new runner with said config each time we want
+        // to simulate a new blockchain.
+        // We could increase N but chain_id would be the same for each run and overwrite
+        // the blockhain_test_n.log
+        // As of 0.9.2, it is not possible to get the current run index for a runner in order
+        // to replace the chain_id with something more elegant.
+        let mut runner = TestRunner::new(config.clone());
+
+        // truncate if the file already exists
+        let output_file = OpenOptions::new()
+            .create(true)
+            .truncate(true)
+            .write(true)
+            .open(format!("blockchain_test_{}.log", chain_id))
+            .unwrap();
+        // close file handle with truncate option
+        drop(output_file);
+
+        runner
+            .run(
+                &chain(
+                    arbitrary_blockchain(),
+                    6,
+                    arbitrary_in_set,
+                    all_receivers,
+                    safety_oracle_at_height,
+                    4,
+                    chain_id,
+                ),
+                |chain| {
+                    chain.iter().for_each(|state| {
+                        let state = state.as_ref().unwrap();
+                        let mut output_file = OpenOptions::new()
+                            .create(true)
+                            .append(true)
+                            .write(true)
+                            .open(format!("blockchain_test_{}.log", chain_id))
+                            .unwrap();
+                        writeln!(
+                            output_file,
+                            "{{lms: {:?},",
+                            state
+                                .iter()
+                                .map(|(_, validator_state)| validator_state.latests_messages())
+                                .collect::<Vec<_>>()
+                        )
+                        .unwrap();
+                        writeln!(output_file, "validatorcount: {:?},", state.keys().len()).unwrap();
+                        writeln!(output_file, "clqs: ").unwrap();
+                        writeln!(output_file, "{:?}}},", clique_collection(state.clone())).unwrap();
+                    });
+                    Ok(())
+                },
+            )
+            .unwrap();
+    }
+}
+
+#[test]
+fn leader_blockchain() {
+    let mut config = Config::with_cases(1);
+    config.source_file = Some("tests/generative_tests.rs");
+
+    // validator 0 proposes five times as often as any other validator, modeling a
+    // skewed leader rotation rather than arbitrary_in_set's uniform pick.
+    let mut weights = HashMap::new();
+    weights.insert(0u32, 5.0);
+
+    for chain_id in 0..10 {
+        let mut runner = TestRunner::new(config.clone());
+
+        let output_file = OpenOptions::new()
+            .create(true)
+            .truncate(true)
+            .write(true)
+            .open(format!("leader_blockchain_test_{}.log", chain_id))
+            .unwrap();
+        drop(output_file);
+
+        runner
+            .run(
+                &chain(
+                    arbitrary_blockchain(),
+                    6,
+                    leader_sequence(weights.clone()),
+                    all_receivers,
+                    safety_oracle_at_height,
+                    4,
+                    chain_id,
+                ),
+                |chain| {
+                    chain.iter().for_each(|state| {
+                        let state = state.as_ref().unwrap();
+                        let mut output_file = OpenOptions::new()
+                            .create(true)
+                            .append(true)
+                            .write(true)
+                            .open(format!("leader_blockchain_test_{}.log", chain_id))
+                            .unwrap();
+                        writeln!(
+                            output_file,
+                            "{{lms: {:?},",
+                            state
+                                .iter()
+                                .map(|(_, validator_state)| validator_state.latests_messages())
+                                .collect::<Vec<_>>()
+                        )
+                        .unwrap();
+                        writeln!(output_file, "validatorcount: {:?},", state.keys().len()).unwrap();
+                        writeln!(output_file, "clqs: ").unwrap();
+                        writeln!(output_file, "{:?}}},", clique_collection(state.clone())).unwrap();
+                    });
+                    Ok(())
+                },
+            )
+            .unwrap();
+    }
+}
+
+proptest! {
+    #![proptest_config(Config::with_cases(30))]
+    #[test]
+    fn round_robin_vote_count(
+        ref chain in chain(
+            VoteCount::arbitrary(),
+            5,
+            round_robin,
+            all_receivers,
+            full_consensus,
+            0,
+            0,
+        ),
+    ) {
+        assert_eq!(
+            chain.last().unwrap().as_ref().unwrap_or(&HashMap::new()).keys().len(),
+            chain.len(),
+            "round robin with n validators should converge in n messages",
+        );
+    }
+}
+
+fn boolwrapper_gen() -> BoxedStrategy<BoolWrapper> {
+    any::<bool>().prop_map(BoolWrapper::new).boxed()
+}
+
+fn integerwrapper_gen() -> BoxedStrategy<IntegerWrapper> {
+    any::<u32>().prop_map(IntegerWrapper::new).boxed()
+}
+
+proptest! {
+    #![proptest_config(Config::with_cases(30))]
+    #[test]
+    fn round_robin_binary(
+        ref chain in chain(
             boolwrapper_gen(),
             15,
             round_robin,
@@ -842,6 +1722,144 @@ proptest! {
     }
 }
 
+proptest! {
+    #![proptest_config(Config::with_cases(1))]
+    #[test]
+    fn byzantine_messenger_vote_count(
+        ref chain in chain_byzantine(
+            VoteCount::arbitrary(),
+            8,
+            byzantine_producer,
+            some_receivers,
+            full_consensus,
+            0,
+            0,
+            HashSet::from_iter(vec![0u32]),
+            VoteCount::toggled_vote,
+        ),
+    ) {
+        // total messages until unilateral consensus among the honest validators, despite
+        // validator 0 equivocating every time it is selected
+        println!(
+            "{} validators -> {:?} message(s)",
+            match chain
+                .last()
+                .unwrap()
+                .as_ref()
+                .unwrap_or(&HashMap::new())
+                .keys()
+                .len()
+                .to_string()
+                .as_ref()
+            {
+                "0" => "Unknown",
+                x => x,
+            },
+            chain.len(),
+        );
+    }
+}
+
+proptest! {
+    #![proptest_config(Config::with_cases(1))]
+    #[test]
+    fn asynchronous_messenger_vote_count(
+        ref chain in chain_async(
+            VoteCount::arbitrary(),
+            8,
+            arbitrary_in_set,
+            all_receivers,
+            full_consensus,
+            0,
+            0,
+            0..=3,
+            0.1,
+            None,
+            200,
+        ),
+    ) {
+        // under delayed/dropped delivery, consensus is not guaranteed within a fixed
+        // round budget; the harness must still run every round without panicking and
+        // never reconstruct an estimate recipients can't reproduce (`add_messages`'s own
+        // check would already have turned that into an `Err`).
+        assert!(
+            chain.iter().all(Result::is_ok),
+            "asynchronous delivery must never desynchronize a recipient's reconstructed estimate",
+        );
+    }
+}
+
+proptest! {
+    #![proptest_config(Config::with_cases(1))]
+    #[test]
+    fn skewed_weight_messenger_vote_count(
+        ref chain in chain_weighted(
+            VoteCount::arbitrary(),
+            8,
+            skewed_weights,
+            arbitrary_in_set,
+            some_receivers,
+            full_consensus,
+            0,
+            0,
+        ),
+    ) {
+        // a skewed validator-weight distribution (a heavy third, a light two-thirds) must
+        // still let the unweighted uniform case's consensus_satisfied eventually converge
+        assert!(
+            chain.iter().all(Result::is_ok),
+            "non-uniform validator weights must not prevent the safety oracle from converging",
+        );
+    }
+}
+
+proptest! {
+    #![proptest_config(Config::with_cases(1))]
+    #[test]
+    fn adversarial_weight_messenger_vote_count(
+        ref chain in chain_weighted(
+            VoteCount::arbitrary(),
+            8,
+            adversarial_weights_under_threshold,
+            arbitrary_in_set,
+            some_receivers,
+            full_consensus,
+            0,
+            0,
+        ),
+    ) {
+        // a colluding subset weighted just under 1/3 of the total stays below the
+        // fault-tolerance bound, so the safety oracle should still converge
+        assert!(
+            chain.iter().all(Result::is_ok),
+            "a sub-1/3 adversarial weight share must not prevent convergence",
+        );
+    }
+}
+
+proptest! {
+    #![proptest_config(Config::with_cases(1))]
+    #[test]
+    fn uniform_weight_messenger_vote_count(
+        ref chain in chain_weighted(
+            VoteCount::arbitrary(),
+            8,
+            uniform_weights,
+            arbitrary_in_set,
+            some_receivers,
+            full_consensus,
+            0,
+            0,
+        ),
+    ) {
+        // baseline: chain_weighted with uniform_weights should behave like chain() itself
+        assert!(
+            chain.iter().all(Result::is_ok),
+            "uniform weights via chain_weighted must converge just as chain() does",
+        );
+    }
+}
+
 prop_compose! {
     fn votes(validators: usize, equivocations: usize)
         (
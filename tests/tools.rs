@@ -0,0 +1,184 @@
+//! Shared helpers for `generative_tests.rs`: the `ChainData` record written to
+//! `stats{chain_id}.log` by `chain` (`chain_weighted`/`chain_byzantine`/`chain_async`
+//! collect `ChainData` rows but do not write them out), the chain-height
+//! helper `get_data_from_state` uses to fill in `ChainData::longest_chain`, and the
+//! `Certificate` finality-proof artifact `safety_oracle_at_height` checkpoints.
+
+use std::collections::{BTreeSet, HashSet};
+
+use serde_derive::Serialize;
+
+use core_cbc_casper::blockchain::Block;
+use core_cbc_casper::justification::{Justification, LatestMessages, LatestMessagesHonest};
+use core_cbc_casper::message::Message;
+use core_cbc_casper::validator;
+use core_cbc_casper::ValidatorNameBlockData;
+
+/// One row of `stats{chain_id}.log`: a snapshot of a single validator's view of chain
+/// convergence at the point a new message was processed. `finality_latency` and
+/// `clique_weight` are filled in only once a safety oracle is actually detected at the
+/// height being tracked; until then they stay `None`/`0.0`.
+#[derive(Clone, Debug)]
+pub struct ChainData {
+    pub chain_id: u32,
+    pub validator_count: u32,
+    pub validator_id: u32,
+    pub consensus_height: i64,
+    pub longest_chain: u32,
+    pub nb_messages: usize,
+    /// Messages elapsed between a finalized block first appearing in any validator's
+    /// `latests_messages` and the round a safety oracle at its height was first detected.
+    pub finality_latency: Option<u32>,
+    /// Total validator weight in the clique `Block::safety_oracles` returned for the
+    /// finalized block, summed from the returned btreeset of validator names.
+    pub clique_weight: f64,
+    /// Finality-proof artifact checkpointed this round, if `safety_oracle_at_height`
+    /// landed on a `CHECKPOINT_EVERY` boundary while a safety oracle was detected.
+    pub certificate: Option<Certificate>,
+}
+
+impl ChainData {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        chain_id: u32,
+        validator_count: u32,
+        validator_id: u32,
+        consensus_height: i64,
+        longest_chain: u32,
+        nb_messages: usize,
+    ) -> Self {
+        ChainData {
+            chain_id,
+            validator_count,
+            validator_id,
+            consensus_height,
+            longest_chain,
+            nb_messages,
+            finality_latency: None,
+            clique_weight: 0.0,
+            certificate: None,
+        }
+    }
+
+    /// Records that a safety oracle at the tracked height was just detected, having taken
+    /// `finality_latency` messages since the finalized block was first seen, with the
+    /// detecting clique collectively weighing `clique_weight`.
+    pub fn record_finality(&mut self, finality_latency: u32, clique_weight: f64) {
+        self.finality_latency = Some(finality_latency);
+        self.clique_weight = clique_weight;
+    }
+
+    /// Attaches this round's periodic finality certificate, once
+    /// `safety_oracle_at_height` has built and self-verified one.
+    pub fn attach_certificate(&mut self, certificate: Certificate) {
+        self.certificate = Some(certificate);
+    }
+}
+
+impl std::fmt::Display for ChainData {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{},{},{},{},{},{},{},{}",
+            self.chain_id,
+            self.validator_count,
+            self.validator_id,
+            self.consensus_height,
+            self.longest_chain,
+            self.nb_messages,
+            self.finality_latency
+                .map(|latency| latency.to_string())
+                .unwrap_or_else(|| "-".to_string()),
+            self.clique_weight,
+        )
+    }
+}
+
+/// Height of the block `latest_messages_honest` would currently estimate, walking
+/// `prevblock` links back to genesis. Used to fill in `ChainData::longest_chain`, the
+/// "how far has this validator's view of the canonical chain grown" figure.
+pub fn get_height_selected_chain<V: validator::ValidatorName>(
+    latest_messages_honest: &LatestMessagesHonest<Block<V>>,
+    validator_state: &validator::State<Block<V>, f64>,
+) -> u32 {
+    let estimate = match latest_messages_honest.make_estimate(validator_state.validators_weights())
+    {
+        Ok(estimate) => estimate,
+        Err(_) => return 0,
+    };
+
+    let mut height = 0;
+    let mut current = Some(estimate);
+    while let Some(block) = current {
+        height += 1;
+        current = block.prevblock();
+    }
+    height
+}
+
+/// Serializable proof that a block reached a safety oracle at a given height: the
+/// finalized block, the height it was detected at, the clique of validators whose
+/// `Block::safety_oracles` result satisfied `safety_threshold`, their summed weight, and
+/// each clique member's own latest message, so `verify` can replay the justification DAG
+/// and recheck the clique without access to the rest of the run. Borrows the
+/// periodic-justification idea from GRANDPA: `safety_oracle_at_height` checkpoints one of
+/// these every `CHECKPOINT_EVERY` rounds rather than on every finalized block.
+#[derive(Clone, Debug, Serialize)]
+pub struct Certificate {
+    pub chain_id: u32,
+    pub round: u32,
+    pub height: u32,
+    pub finalized_block: Block<ValidatorNameBlockData<u32>>,
+    pub clique: BTreeSet<u32>,
+    pub clique_weight: f64,
+    pub safety_threshold: f64,
+    messages: Vec<Message<Block<ValidatorNameBlockData<u32>>>>,
+}
+
+impl Certificate {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        chain_id: u32,
+        round: u32,
+        height: u32,
+        finalized_block: Block<ValidatorNameBlockData<u32>>,
+        clique: BTreeSet<u32>,
+        clique_weight: f64,
+        safety_threshold: f64,
+        messages: Vec<Message<Block<ValidatorNameBlockData<u32>>>>,
+    ) -> Self {
+        Certificate {
+            chain_id,
+            round,
+            height,
+            finalized_block,
+            clique,
+            clique_weight,
+            safety_threshold,
+            messages,
+        }
+    }
+
+    /// Replays this certificate's stored messages through a fresh `Justification` and
+    /// reruns `Block::safety_oracles` against `finalized_block`, returning whether the
+    /// clique this certificate claims still meets `safety_threshold` -- i.e. whether the
+    /// certificate is a valid finality proof independent of the run that produced it.
+    pub fn verify(&self, validators_weights: &validator::Weights<u32, f64>) -> bool {
+        let mut justification = Justification::empty();
+        for message in &self.messages {
+            justification.insert(message.clone());
+        }
+        let latest_messages_honest = LatestMessagesHonest::from_latest_messages(
+            &LatestMessages::from(&justification),
+            &HashSet::new(),
+        );
+        let cliques = Block::safety_oracles(
+            self.finalized_block.clone(),
+            &latest_messages_honest,
+            &HashSet::new(),
+            self.safety_threshold,
+            validators_weights,
+        );
+        cliques.iter().any(|clique| clique == &self.clique)
+    }
+}